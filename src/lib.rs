@@ -5,36 +5,237 @@
 //! [crate documentation](crate)
 
 use core::str::FromStr;
-use proc_macro::TokenTree::{Group, Ident, Punct};
+use proc_macro::TokenTree::{self, Group, Ident, Literal, Punct};
 use proc_macro::{token_stream::IntoIter, Delimiter, Delimiter::Brace, Spacing, Span, TokenStream};
-use proc_macro::{Group as Gr, Ident as Idn, Punct as Pn};
+use proc_macro::{Group as Gr, Ident as Idn, Literal as Lit, Punct as Pn};
 use std::iter::once;
 
 enum ParseStates {
     Start,
     Vis,
     Name,
+    Lt,
     Args,
     Minus,
     Gt,
     Out,
+    Where,
 }
-use ParseStates::{Args, Gt, Minus, Name, Out, Start, Vis};
+use ParseStates::{Args, Gt, Lt, Minus, Name, Out, Start, Vis, Where};
 
 // region: region gen
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct Attr {
     enum_name: String,
     enum_ident: Option<Idn>,
     run_method: String,
+    run_ident: Option<Idn>,
     drv_dbg: bool,
     out_ident: Option<Idn>,
     out_dbg: bool,
     strict_types: bool,
+    /// option `named`: struct-like variants with the argument names as fields
+    named: bool,
+    /// option `try`: methods return `Result<T, {OutName}Mismatch>` instead of panicking
+    try_out: Option<Idn>,
+    /// option `fallback = f`: `Self::f("method", out)` instead of panicking
+    fallback: Option<Idn>,
+    /// option `owned`: `{EnumName}Owned` with owned arguments and `apply()`
+    owned: bool,
+    /// options `before = hook` and `after = hook`: called around the handler
+    before: Option<Idn>,
+    after: Option<Idn>,
+    /// option `bind = name`: the result of the handler for default bodies (instead of `_out`)
+    bind: Option<Idn>,
+    /// option `serde` (cargo feature `serde`): `Serialize` and `Deserialize` for the generated enums
+    serde: bool,
+    /// option `trace` (cargo feature `tracing`): a `tracing` span in each generated method
+    trace: bool,
+    /// options `derive(..)` and `attr(..)` for *EnumName* (except `Debug`)
+    derives: Vec<String>,
+    attrs: Vec<String>,
+    /// options `out_derive(..)` and `out_attr(..)` for *OutName* (except `Debug`)
+    out_derives: Vec<String>,
+    out_attrs: Vec<String>,
+    /// option `parts(..)`: paths to the macros of the `#[gen_part]` blocks
+    parts: Vec<Vec<TokenTree>>,
+    /// options `to(EnumName, handler_name)`: the enums of the signatures tagged `#[to(EnumName)]`
+    to: Vec<(Idn, Idn, bool)>,
+    /// the lifetime of the generated enums in place of the elided lifetimes:
+    /// `'a` or, if the block uses `'a`, a fresh name (see `elided_name()`)
+    elided: String,
 }
 impl Attr {
-    fn new(attr_ts: TokenStream) -> Attr {
+    fn new(attr_ts: TokenStream) -> Result<Attr, TokenStream> {
+        let mut head: Vec<TokenTree> = attr_ts.into_iter().collect();
+        let opts = match head.iter().position(|tt| is_punct(tt, ';')) {
+            Some(i) => head.split_off(i).into_iter().skip(1).collect(),
+            None => TokenStream::new(),
+        };
+        Attr::new_head(TokenStream::from_iter(head))?.options(opts)
+    }
+
+    /// parses the options after `;`: `option_name`, `option_name(..)` or `option_name = value`
+    fn options(mut self, opts: TokenStream) -> Result<Attr, TokenStream> {
+        for opt in split_top(opts, ',') {
+            match &opt[..] {
+                [] => (),
+                [Ident(id)] if id.to_string() == "named" => self.named = true,
+                [Ident(id)] if id.to_string() == "owned" => self.owned = true,
+                [Ident(id)] if id.to_string() == "serde" => {
+                    if !cfg!(feature = "serde") {
+                        return Err(compile_error(
+                            "#[gen]: option `serde` requires the `serde` feature of methods-enum",
+                            id.span(),
+                        ));
+                    }
+                    self.serde = true;
+                    self.named = true
+                }
+                [Ident(id)] if id.to_string() == "trace" => {
+                    if !cfg!(feature = "tracing") {
+                        return Err(compile_error(
+                            "#[gen]: option `trace` requires the `tracing` feature of methods-enum",
+                            id.span(),
+                        ));
+                    }
+                    self.trace = true
+                }
+                [Ident(id)] if id.to_string() == "try" => {
+                    self.need_out(id)?;
+                    self.try_out = Some(id.clone())
+                }
+                [Ident(id), Group(gr)] if gr.delimiter() == Delimiter::Parenthesis => {
+                    let opt = id.to_string();
+                    if opt.starts_with("out_") {
+                        self.need_out(id)?;
+                    }
+                    match &opt[..] {
+                        "derive" | "out_derive" => {
+                            for d in split_top(gr.stream(), ',').iter().filter(|d| !d.is_empty()) {
+                                let d = ty_str_raw(d);
+                                match (&opt[..], &d[..]) {
+                                    ("derive", "Debug") => self.drv_dbg = true,
+                                    ("out_derive", "Debug") => self.out_dbg = true,
+                                    ("derive", _) => self.derives.push(d),
+                                    _ => self.out_derives.push(d),
+                                }
+                            }
+                        }
+                        "attr" => self.attrs.push(gr.stream().to_string()),
+                        "parts" => {
+                            self.parts = split_top(gr.stream(), ',');
+                            self.parts.retain(|p| !p.is_empty());
+                        }
+                        "out_attr" => self.out_attrs.push(gr.stream().to_string()),
+                        "to" => match &gr.stream().into_iter().collect::<Vec<_>>()[..] {
+                            [Ident(e), Punct(p), Ident(h)] if ",:".contains(p.as_char()) => {
+                                self.to.push((e.clone(), h.clone(), p.as_char() == ':'))
+                            }
+                            _ => {
+                                return Err(compile_error(
+                                    "#[gen]: Syntax error in option `to(..)`, \
+expected: `to(EnumName, handler_name)` or `to(EnumName: handler_name)`",
+                                    gr.span(),
+                                ))
+                            }
+                        },
+                        _ => {
+                            return Err(compile_error(
+                                &format!("#[gen]: unknown or malformed option `{id}`"),
+                                id.span(),
+                            ))
+                        }
+                    }
+                }
+                [Ident(id), Punct(p), Ident(hook)]
+                    if ["before", "after"].contains(&&id.to_string()[..]) && p.as_char() == '=' =>
+                {
+                    match &id.to_string()[..] {
+                        "before" => self.before = Some(hook.clone()),
+                        _ => self.after = Some(hook.clone()),
+                    }
+                }
+                [Ident(id), Punct(p), Ident(name)]
+                    if id.to_string() == "bind" && p.as_char() == '=' =>
+                {
+                    self.bind = Some(name.clone())
+                }
+                [Ident(id), Punct(p), Ident(f)]
+                    if id.to_string() == "fallback" && p.as_char() == '=' =>
+                {
+                    self.need_out(id)?;
+                    self.fallback = Some(f.clone())
+                }
+                [Ident(id), ..] => {
+                    return Err(compile_error(
+                        &format!("#[gen]: unknown or malformed option `{id}`"),
+                        id.span(),
+                    ))
+                }
+                [tt, ..] => {
+                    return Err(compile_error(
+                        "#[gen]: Syntax error in attribute options after `;`, \
+expected: `option_name`, `option_name(..)` or `option_name = value`",
+                        tt.span(),
+                    ))
+                }
+            }
+        }
+        if let (Some(_), Some(f)) = (&self.try_out, &self.fallback) {
+            return Err(compile_error(
+                "#[gen]: options `try` and `fallback` cannot be used together",
+                f.span(),
+            ));
+        }
+        Ok(self)
+    }
+
+    /// the attribute of the `i`-th enum of the block: *EnumName* or the enum of the option `to(..)`,
+    /// options `owned`, `before` and `after` are only for *EnumName*
+    fn group(&self, i: usize) -> Attr {
+        match i.checked_sub(1).and_then(|i| self.to.get(i)) {
+            Some((e, h, dbg)) => Attr {
+                enum_name: e.to_string(),
+                enum_ident: Some(e.clone()),
+                run_method: h.to_string(),
+                run_ident: Some(h.clone()),
+                drv_dbg: *dbg,
+                owned: false,
+                before: None,
+                after: None,
+                ..self.clone()
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// the names of the handlers of all enums of the block
+    fn handlers(&self) -> Vec<String> {
+        once(self.run_method.clone()).chain(self.to.iter().map(|t| t.1.to_string())).collect()
+    }
+
+    /// for the option `serde`: `Serialize` and `Deserialize` from `::serde`
+    fn serde_derives(&self) -> Vec<String> {
+        match self.serde {
+            true => vec!["::serde::Serialize".to_string(), "::serde::Deserialize".to_string()],
+            false => Vec::new(),
+        }
+    }
+
+    fn need_out(&self, opt: &Idn) -> Result<(), TokenStream> {
+        match self.out_ident {
+            Some(_) => Ok(()),
+            None => Err(compile_error(
+                &format!("#[gen]: option `{opt}` requires OutName in the attribute"),
+                opt.span(),
+            )),
+        }
+    }
+
+    /// parses `EnumName ,|: handler_name ,|= OutName !?` before `;`
+    fn new_head(attr_ts: TokenStream) -> Result<Attr, TokenStream> {
         let mut attr_it = attr_ts.into_iter();
         let attr = match [attr_it.next(), attr_it.next(), attr_it.next()] {
             [Some(Ident(id)), Some(Punct(p)), Some(Ident(r_id))] if ",:".contains(p.as_char()) => {
@@ -42,81 +243,337 @@ impl Attr {
                     enum_name: id.to_string(),
                     enum_ident: Some(id),
                     run_method: r_id.to_string(),
+                    run_ident: Some(r_id),
                     drv_dbg: p.as_char() == ':',
                     ..Default::default()
                 }
             }
-            _ => panic!("#[gen]: Syntax error in attribute #[methods_enum::gen(?? "),
+            tts => {
+                let bad = match &tts {
+                    [Some(Ident(_)), Some(Punct(p)), tt] if ",:".contains(p.as_char()) => tt,
+                    [Some(Ident(_)), tt, _] => tt,
+                    [tt, ..] => tt,
+                };
+                return Err(compile_error(
+                    "#[gen]: Syntax error in attribute, expected: \
+`#[methods_enum::gen(EnumName, handler_name)]` or `#[methods_enum::gen(EnumName, handler_name, OutName)]`",
+                    bad.as_ref().map_or(Span::call_site(), |tt| tt.span()),
+                ));
+            }
         };
         match [attr_it.next(), attr_it.next()] {
-            [None, None] => attr,
-            [Some(Punct(p)), Some(Ident(out_id))] if ",=".contains(p.as_char()) => Attr {
+            [None, None] => Ok(attr),
+            [Some(Punct(p)), Some(Ident(out_id))] if ",=".contains(p.as_char()) => Ok(Attr {
                 out_ident: Some(out_id),
                 out_dbg: p.as_char() == '=',
                 strict_types: matches!(attr_it.next(), Some(Punct(p)) if p.as_char() == '!'),
                 ..attr
-            },
-            _ => panic!(
-                "#[gen]: Syntax error in attribute #[methods_enum::gen({}:{}??..",
-                attr.enum_name, attr.run_method
-            ),
+            }),
+            [Some(tt), _] | [_, Some(tt)] => Err(compile_error(
+                &format!(
+                    "#[gen]: Syntax error in attribute #[methods_enum::gen({}, {}, ..)]: \
+expected `, OutName` or `= OutName`",
+                    attr.enum_name, attr.run_method
+                ),
+                tt.span(),
+            )),
         }
     }
 }
 
+/// the header of the `impl` block: `impl<..> SelfType where ..`
+#[derive(Default)]
+struct ImplHead {
+    /// generic parameters: (name, declaration)
+    params: Vec<(String, Vec<TokenTree>)>,
+    /// `where` predicates: (name of the bounded parameter, predicate)
+    preds: Vec<(String, Vec<TokenTree>)>,
+    self_ty: String,
+    /// for `impl Trait for Type`
+    trait_ty: Option<String>,
+}
+impl ImplHead {
+    fn new(header: &[TokenTree]) -> ImplHead {
+        let mut head = ImplHead::default();
+        let mut it = header.iter().cloned().peekable();
+        if it.peek().is_some_and(|tt| is_punct(tt, '<')) {
+            it.next();
+            let (mut lg, mut dash) = (1, false);
+            let gens = TokenStream::from_iter(it.by_ref().take_while(|tt| {
+                match tt {
+                    Punct(p) if p.as_char() == '<' => lg += 1,
+                    Punct(p) if p.as_char() == '>' && !dash => lg -= 1,
+                    _ => (),
+                }
+                dash = is_punct(tt, '-');
+                lg > 0
+            }));
+            for param in split_top(gens, ',') {
+                let decl = param.iter().take_while(|tt| !is_punct(tt, '=')).cloned().collect();
+                head.params.push((param_name(&param), decl));
+            }
+        }
+        let mut self_ty: Vec<_> = it.by_ref().take_while(|tt| !is_ident(tt, "where")).collect();
+        let mut lg = 0;
+        let for_pos = self_ty.iter().position(|tt| {
+            match tt {
+                Punct(p) if p.as_char() == '<' => lg += 1,
+                Punct(p) if p.as_char() == '>' => lg -= 1,
+                _ => (),
+            }
+            lg == 0 && is_ident(tt, "for")
+        });
+        if let Some(i) = for_pos {
+            head.trait_ty = Some(ty_str_raw(&self_ty[..i]));
+            self_ty.drain(..=i);
+        }
+        head.self_ty = ty_str_raw(&self_ty);
+        for pred in split_top(TokenStream::from_iter(it), ',') {
+            head.preds.push((param_name(&pred), pred));
+        }
+        head
+    }
+
+    /// generic parameters and `where` predicates of the `impl` block that are not in `usage`
+    fn rest(&self, usage: &str) -> Gens {
+        let used: Vec<&str> = usage.trim_matches(['<', '>']).split(", ").collect();
+        let decl = (self.params.iter()).filter(|(n, _)| !used.contains(&&n[..])).map(|(_, d)| d);
+        let preds = (self.preds.iter())
+            .filter(|(n, p)| !used.contains(&&n[..]) && p.iter().any(|tt| is_punct(tt, ':')))
+            .map(|(_, p)| (ty_str_raw(p), p.clone()))
+            .collect();
+        Gens::new(decl.map(|d| (ty_str_raw(d), d.clone())).collect(), Vec::new(), preds)
+    }
+
+    /// replaces `Self` in the type tokens with the type of the `impl` block located at `Self`
+    fn unself(&self, ty: &[TokenTree]) -> Vec<TokenTree> {
+        let mut res = Vec::new();
+        for (i, tt) in ty.iter().enumerate() {
+            match tt {
+                Ident(id) if id.to_string() == "Self" => {
+                    let self_ty = match (&self.trait_ty, ty.get(i + 1)) {
+                        (Some(tr), Some(p)) if is_punct(p, ':') => {
+                            format!("<{} as {tr}>", self.self_ty)
+                        }
+                        (None, Some(p)) if is_punct(p, ':') => format!("<{}>", self.self_ty),
+                        _ => self.self_ty.clone(),
+                    };
+                    res.extend(spanned(&self_ty, id.span()));
+                }
+                Group(gr) => {
+                    let inner: Vec<TokenTree> = gr.stream().into_iter().collect();
+                    let mut g =
+                        Gr::new(gr.delimiter(), TokenStream::from_iter(self.unself(&inner)));
+                    g.set_span(gr.span());
+                    res.push(Group(g));
+                }
+                tt => res.push(tt.clone()),
+            }
+        }
+        res
+    }
+
+    /// generic parameters for the generated `enum` with the types `typs`
+    /// (with the lifetime `elided` first)
+    fn generics<'s>(&self, typs: impl Iterator<Item = &'s String>, elided: &str) -> Gens {
+        let typs: Vec<&String> = typs.collect();
+        let lts = lftms(typs.iter().copied(), elided);
+        let (mut decl, mut usage) = (Vec::new(), Vec::new());
+        for lt in &lts {
+            decl.push(match self.params.iter().find(|(n, _)| n == lt) {
+                Some((_, d)) => bounded(d, &lts),
+                None => (lt.clone(), TokenStream::from_str(lt).unwrap().into_iter().collect()),
+            });
+            usage.push(lt.clone());
+        }
+        for (name, d) in self.params.iter().filter(|(n, _)| !n.starts_with('\'')) {
+            if typs.iter().any(|s| has_word(s, name)) {
+                decl.push(bounded(d, &lts));
+                usage.push(name.clone());
+            }
+        }
+        if decl.is_empty() {
+            return Gens::default();
+        }
+        let preds = (self.preds.iter())
+            .filter(|(n, _)| usage.contains(n))
+            .map(|(_, p)| bounded(p, &lts))
+            .filter(|p| p.0.contains(':'))
+            .collect();
+        Gens::new(decl, usage, preds)
+    }
+}
+
+/// generic parameters of a generated item: as strings for the doc and as the tokens
+/// of the `impl` block for the code, so that their errors are not repeated at the attribute
+#[derive(Default)]
+struct Gens {
+    /// `<'a, T: Bound>`
+    decl: String,
+    decl_ts: Vec<TokenTree>,
+    /// `<'a, T>`
+    usage: String,
+    /// `where` predicates
+    preds: Vec<(String, Vec<TokenTree>)>,
+}
+impl Gens {
+    fn new(
+        decl: Vec<(String, Vec<TokenTree>)>,
+        usage: Vec<String>,
+        preds: Vec<(String, Vec<TokenTree>)>,
+    ) -> Gens {
+        if decl.is_empty() {
+            return Gens { preds, ..Gens::default() };
+        }
+        let mut decl_ts = vec![Punct(Pn::new('<', Spacing::Alone))];
+        decl_ts.extend(join_ts(decl.iter().map(|d| d.1.clone()), ','));
+        decl_ts.push(Punct(Pn::new('>', Spacing::Alone)));
+        let decl: Vec<&str> = decl.iter().map(|d| &d.0[..]).collect();
+        Gens {
+            decl: format!("<{}>", decl.join(", ")),
+            decl_ts,
+            usage: if usage.is_empty() { String::new() } else { format!("<{}>", usage.join(", ")) },
+            preds,
+        }
+    }
+
+    /// the `where` clause with the additional predicates `more`: (string, tokens)
+    fn wh(&self, more: &[(String, Vec<TokenTree>)]) -> (String, Vec<TokenTree>) {
+        let preds: Vec<&(String, Vec<TokenTree>)> = self.preds.iter().chain(more).collect();
+        if preds.is_empty() {
+            return (String::new(), Vec::new());
+        }
+        let wh: Vec<&str> = preds.iter().map(|p| &p.0[..]).collect();
+        let mut wh_ts = vec![Ident(Idn::new("where", Span::call_site()))];
+        wh_ts.extend(join_ts(preds.iter().map(|p| p.1.clone()), ','));
+        (format!(" where {}", wh.join(", ")), wh_ts)
+    }
+}
+
+/// the predicate `ty: bound` for the `where` clause from `ty` (string, tokens): (string, tokens)
+fn pred((ty, ty_ts): (&str, &[TokenTree]), bound: &str) -> (String, Vec<TokenTree>) {
+    let mut ts = ty_ts.to_vec();
+    ts.extend(TokenStream::from_str(&format!(": {bound}")).unwrap());
+    (format!("{ty}: {bound}"), ts)
+}
+
+/// the token lists separated by `sep`
+fn join_ts(parts: impl Iterator<Item = Vec<TokenTree>>, sep: char) -> Vec<TokenTree> {
+    let mut res = Vec::new();
+    for (i, part) in parts.enumerate() {
+        if i > 0 {
+            res.push(Punct(Pn::new(sep, Spacing::Alone)));
+        }
+        res.extend(part);
+    }
+    res
+}
+
+/// the name of a generic parameter or of a parameter bounded by a `where` predicate
+fn param_name(param: &[TokenTree]) -> String {
+    match param {
+        [Punct(_), Ident(id), ..] => format!("'{id}"),
+        [Ident(c), Ident(id), ..] if c.to_string() == "const" => id.to_string(),
+        [Ident(id), ..] => id.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// a generic parameter declaration or `where` predicate without bounds
+/// with lifetimes other than `lts`: (string, tokens)
+fn bounded(decl: &[TokenTree], lts: &[String]) -> (String, Vec<TokenTree>) {
+    let Some(i) = decl.iter().position(|tt| is_punct(tt, ':')) else {
+        return (ty_str_raw(decl), decl.to_vec());
+    };
+    if is_ident(&decl[0], "const") {
+        return (ty_str_raw(decl), decl.to_vec());
+    }
+    let bounds: Vec<(String, Vec<TokenTree>)> =
+        split_top(TokenStream::from_iter(decl[i + 1..].iter().cloned()), '+')
+            .into_iter()
+            .map(|b| (ty_str_raw(&b), b))
+            .filter(|b| lftms(once(&b.0), "").iter().all(|lt| lts.contains(lt)))
+            .collect();
+    let (head, mut ts) = (ty_str_raw(&decl[..i]), decl[..i].to_vec());
+    if bounds.is_empty() {
+        return (head, ts);
+    }
+    ts.push(decl[i].clone());
+    ts.extend(join_ts(bounds.iter().map(|b| b.1.clone()), '+'));
+    let bounds: Vec<&str> = bounds.iter().map(|b| &b.0[..]).collect();
+    (format!("{head}: {}", bounds.join(" + ")), ts)
+}
+
 #[derive(Default)]
 struct Meth {
     ident: Option<Idn>,
     prev_ts: TokenStream,
     vis: TokenStream,
+    generics: TokenStream,
     args: TokenStream,
-    params: String,
-    typs: String,
+    /// (argument name, type, value expression): the name and the expression are the binding
+    /// or, for a destructuring pattern, its bindings joined with `_` and the pattern rebuilt as an expression
+    arg_tys: Vec<(Idn, Vec<TokenTree>, Vec<TokenTree>)>,
+    /// an argument with `mut` in its pattern
+    mut_arg: bool,
+    /// `compile_error!` for a signature that cannot be included in `enum`
+    err: Option<TokenStream>,
+    /// argument names
+    names: Vec<String>,
+    /// argument expressions for the variant fields
+    params: Vec<String>,
+    /// `params` as tokens with the spans of the signature
+    param_ts: Vec<Vec<TokenTree>>,
+    /// variant field types
+    typs: Vec<String>,
+    /// `typs` as tokens with the spans of the signature
+    typ_ts: Vec<Vec<TokenTree>>,
     out_span: Option<Span>,
+    /// number of tokens in `prev_ts` before the return type
+    out_at: usize,
     out: TokenStream,
+    wh: TokenStream,
     body: TokenStream,
+    /// the tag `#[to(EnumName)]` of the signature
+    to: Option<Idn>,
+    /// the outer attributes of the signature (the groups `[..]` after `#`)
+    attrs: Vec<Gr>,
+    /// `async fn`
+    is_async: bool,
+    /// the tag `#[bind(name)]`: the binding of the result of the handler for the body
+    bind: Option<Idn>,
+    /// the index of the enum of the signature: 0 - *EnumName*, then the enums of `to(..)`
+    group: usize,
 }
 
 impl Meth {
     /// on successful parsing of the arguments returns `Minus`, otherwise - `Start`
     fn args_parsing(&mut self, args_gr: Gr) -> ParseStates {
-        let mut args_it = args_gr.stream().into_iter();
-        let mut lg = 0;
-        let mut first = true;
-        let mut is_self = false;
-        self.params = String::new();
-        self.typs = String::new();
-        let st = loop {
-            match args_it.next() {
-                Some(Punct(p)) if p.as_char() == ',' && lg == 0 => {
-                    match [args_it.next(), args_it.next()] {
-                        [Some(Ident(id)), Some(Punct(p))] if p.as_char() == ':' => {
-                            if first {
-                                if !is_self {
-                                    break Start;
-                                }
-                                first = false;
-                            } else {
-                                self.params.push_str(", ");
-                                self.typs.push_str(", ");
-                            }
-                            self.params.push_str(&id.to_string());
-                        }
-                        [Some(_), _] => break Start,
-                        [None, _] => break if is_self { Minus } else { Start },
-                    }
-                }
-                Some(Punct(p)) if "<>".contains(p.as_char()) => {
-                    lg = lg + if p.as_char() == '<' { 1 } else { -1 };
-                    self.typs.push(p.as_char());
-                }
-                Some(Ident(id)) if id.to_string() == "impl" => break Start,
-                Some(Ident(id)) if first && id.to_string() == "self" => is_self = true,
-                Some(Ident(id)) if !first && id.to_string() == "mut" => self.typs.push_str("mut "),
-                Some(tt) if !first => self.typs.push_str(&tt.to_string()),
-                None => break if is_self { Minus } else { Start },
-                _ => (),
+        let args = split_top(args_gr.stream(), ',');
+        let st = match args.split_first() {
+            Some((slf, rest)) if slf.iter().any(|tt| is_ident(tt, "self")) => {
+                self.arg_tys = Vec::new();
+                self.mut_arg = false;
+                rest.iter()
+                    .filter(|arg| !arg.is_empty())
+                    .enumerate()
+                    .try_for_each(|(i, arg)| {
+                        let (pat, ty) = arg.split_at(type_colon(arg).filter(|&c| c > 0)?);
+                        let ty = &ty[1..];
+                        let mut binds = Vec::new();
+                        let expr = pat_expr(pat, &mut binds)?;
+                        let id = match (pat, &binds[..]) {
+                            ([.., Ident(id)], [_]) if matches!(&expr[..], [Ident(_)]) => id.clone(),
+                            (_, []) => Idn::new(&format!("arg{i}"), pat[0].span()),
+                            _ => Idn::new(&binds.join("_"), pat[0].span()),
+                        };
+                        let pat_s = TokenStream::from_iter(pat.iter().cloned()).to_string();
+                        self.mut_arg |= has_word(&pat_s, "mut");
+                        (!ty.is_empty()).then(|| self.arg_tys.push((id, ty.to_vec(), expr)))
+                    })
+                    .map_or(Start, |_| Minus)
             }
+            _ => Start,
         };
         if let Minus = st {
             self.args = args_gr.stream();
@@ -127,17 +584,356 @@ impl Meth {
         st
     }
 
-    fn prev_extend(&mut self, tt: proc_macro::TokenTree, new_st: ParseStates) -> ParseStates {
+    /// forms `params` and `typs` from `arg_tys`: arguments of the method's generic types
+    /// and of `impl Trait` types are erased to `&dyn` of their bounds,
+    /// the elided lifetimes get the lifetime `elided`
+    fn fin(&mut self, elided: &str) -> Result<(), TokenStream> {
+        let mut bounds: Vec<(String, Vec<TokenTree>)> = Vec::new(); // (type param, its bounds)
+        let mut consts: Vec<String> = Vec::new();
+        for param in split_top(self.generics.clone(), ',') {
+            match &param[..] {
+                [Ident(id), Ident(n), ..] if id.to_string() == "const" => {
+                    consts.push(n.to_string())
+                }
+                [Ident(id), Punct(p), bs @ ..] if p.as_char() == ':' => {
+                    let bs = bs.iter().take_while(|tt| !is_punct(tt, '=')).cloned().collect();
+                    bounds.push((id.to_string(), bs))
+                }
+                [Ident(id), ..] => bounds.push((id.to_string(), Vec::new())),
+                _ => (), // lifetime
+            }
+        }
+        for pred in split_top(self.wh.clone(), ',') {
+            if let [Ident(id), Punct(p), bs @ ..] = &pred[..] {
+                if let (':', Some((_, v))) =
+                    (p.as_char(), bounds.iter_mut().find(|(n, _)| n == &id.to_string()))
+                {
+                    if !v.is_empty() {
+                        v.push(Punct(Pn::new('+', Spacing::Alone)));
+                    }
+                    v.extend(bs.iter().cloned());
+                }
+            }
+        }
+        let generic_names: Vec<&String> = bounds.iter().map(|(n, _)| n).chain(&consts).collect();
+
+        let name = self.ident.as_ref().map(|id| id.to_string()).unwrap_or_default();
+        let (mut names, mut params, mut typ_ts) = (Vec::new(), Vec::new(), Vec::new());
+        for (id, ty, expr) in &self.arg_tys {
+            if let Some(span) = mut_ref_pat(expr) {
+                return Err(compile_error(
+                    &format!(
+                        "#[gen]: the reference pattern `&mut ..` of argument `{id}` of method \
+`{name}()` cannot be rebuilt into the variant: its bindings are copies of the referenced value; \
+bind the argument by name"
+                    ),
+                    span,
+                ));
+            }
+            names.push(id.to_string());
+            let amp = matches!(ty.first(), Some(tt) if is_punct(tt, '&'));
+            let (prefix, tail) = ty.split_at(match &ty[..] {
+                [Punct(_), Punct(q), Ident(_), Ident(m), ..] if amp && q.as_char() == '\'' => {
+                    if m.to_string() == "mut" {
+                        4
+                    } else {
+                        3
+                    }
+                }
+                [Punct(_), Punct(q), ..] if amp && q.as_char() == '\'' => 3,
+                [Punct(_), Ident(m), ..] if amp && m.to_string() == "mut" => 2,
+                [Punct(_), ..] if amp => 1,
+                _ => 0,
+            });
+            let erased = match tail {
+                [Ident(i), bs @ ..] if i.to_string() == "impl" => Some(bs.to_vec()),
+                [Ident(t)] => bounds.iter().find(|(n, _)| n == &t.to_string()).map(|b| b.1.clone()),
+                _ => None,
+            };
+            match erased {
+                Some(bs) => {
+                    let dyn_bs: Vec<Vec<TokenTree>> = split_top(TokenStream::from_iter(bs), '+')
+                        .into_iter()
+                        .filter(|b| !b.is_empty())
+                        .collect();
+                    if let Some(b) = dyn_bs.iter().find(|b| is_punct(&b[0], '?')) {
+                        return Err(compile_error(
+                            &format!(
+                                "#[gen]: the type of argument `{id}` of method `{name}()` \
+is `{}`: an unsized type cannot be erased to `&dyn ..`",
+                                ty_str_raw(b)
+                            ),
+                            b[0].span(),
+                        ));
+                    }
+                    let traits: Vec<&Vec<TokenTree>> =
+                        dyn_bs.iter().filter(|b| !is_punct(&b[0], '\'')).collect();
+                    if traits.is_empty() {
+                        return Err(compile_error(
+                            &format!(
+                                "#[gen]: the type of argument `{id}` of method `{name}()` \
+has no trait bounds to be erased to `&dyn ..`"
+                            ),
+                            id.span(),
+                        ));
+                    }
+                    let mut main_traits = Vec::new();
+                    for b in traits {
+                        let (tr, args) = trait_name(b);
+                        let not_dyn = match &tr[..] {
+                            "PartialEq" | "PartialOrd" => !args,
+                            tr => NOT_DYN.contains(&tr),
+                        };
+                        if not_dyn {
+                            return Err(compile_error(
+                                &format!(
+                                    "#[gen]: the bound `{}` of argument `{id}` of method `{name}()` \
+is not dyn-compatible: the type cannot be erased to `&dyn ..`",
+                                    ty_str_raw(b)
+                                ),
+                                b[0].span(),
+                            ));
+                        }
+                        if !AUTO_TRAITS.contains(&&tr[..]) {
+                            main_traits.push(b);
+                        }
+                    }
+                    if let [first, second, ..] = &main_traits[..] {
+                        return Err(compile_error(
+                            &format!(
+                                "#[gen]: the bounds `{}` and `{}` of argument `{id}` of method \
+`{name}()` cannot be erased to one `&dyn ..`: a trait object can have only one trait \
+besides `Send`, `Sync`, `Unpin` and lifetimes",
+                                ty_str_raw(first),
+                                ty_str_raw(second)
+                            ),
+                            second[0].span(),
+                        ));
+                    }
+                    let span = Span::call_site().located_at(tail[0].span());
+                    let mut amp_p = Pn::new('&', Spacing::Alone);
+                    amp_p.set_span(span);
+                    let prefix = if prefix.is_empty() {
+                        vec![Punct(amp_p.clone())]
+                    } else {
+                        prefix.to_vec()
+                    };
+                    let mut dyn_ts = TokenStream::from(Ident(Idn::new("dyn", span)));
+                    for (i, b) in dyn_bs.into_iter().enumerate() {
+                        if i > 0 {
+                            dyn_ts.extend(once(Punct(Pn::new('+', Spacing::Alone))));
+                        }
+                        dyn_ts.extend(b);
+                    }
+                    let mut typ = elided_lftms(TokenStream::from_iter(prefix), elided);
+                    let mut dyn_gr = Gr::new(Delimiter::Parenthesis, dyn_ts);
+                    dyn_gr.set_span(span);
+                    typ.push(Group(dyn_gr));
+                    typ_ts.push(typ);
+                    let mut param = expr.clone();
+                    if !amp {
+                        param.insert(0, Punct(amp_p));
+                    }
+                    params.push(param);
+                }
+                None => {
+                    if mentions(ty, &generic_names) {
+                        return Err(compile_error(
+                            &format!(
+                                "#[gen]: the generic type of argument `{id}` of method `{name}()` \
+can only be erased to `&dyn ..` in the form `T`, `&T`, `&mut T` or `impl Trait`"
+                            ),
+                            ty[0].span(),
+                        ));
+                    }
+                    typ_ts.push(elided_lftms(TokenStream::from_iter(ty.iter().cloned()), elided));
+                    params.push(expr.clone());
+                }
+            }
+        }
+        let out: Vec<TokenTree> = self.out.clone().into_iter().collect();
+        let out_s = ty_str_raw(&out);
+        if let Some(p) = generic_names.iter().find(|p| has_word(&out_s, p)) {
+            return Err(compile_error(
+                &format!(
+                    "#[gen]: the return type `{}` of method `{name}()` uses its generic parameter \
+`{p}`: the handler cannot return a type chosen by the caller; return a concrete type",
+                    ts_to_doc(&self.out)
+                ),
+                out[0].span(),
+            ));
+        }
+        self.names = names;
+        self.params =
+            params.iter().map(|p| TokenStream::from_iter(p.clone()).to_string()).collect();
+        self.param_ts = params;
+        self.typs = typ_ts.iter().map(|t| ty_str_raw(t)).collect();
+        self.typ_ts = typ_ts;
+        Ok(())
+    }
+
+    /// variant fields: `(T1, T2)` or with option `named` - ` { a: T1, b: T2 }`
+    fn fields(&self, named: bool) -> String {
+        if named {
+            let fs: Vec<_> =
+                self.names.iter().zip(&self.typs).map(|(n, t)| n.clone() + ": " + t).collect();
+            format!(" {{ {} }}", fs.join(", "))
+        } else {
+            format!("({})", self.typs.join(", "))
+        }
+    }
+
+    /// `fields()` as tokens of the argument names and types of the signature
+    fn fields_ts(&self, named: bool) -> TokenTree {
+        let mut ts = TokenStream::new();
+        for ((id, ..), typ) in self.arg_tys.iter().zip(&self.typ_ts) {
+            if named {
+                ts.extend([Ident(id.clone()), Punct(Pn::new(':', Spacing::Alone))]);
+            }
+            ts.extend(typ.iter().cloned());
+            ts.extend(once(Punct(Pn::new(',', Spacing::Alone))));
+        }
+        Group(Gr::new(if named { Brace } else { Delimiter::Parenthesis }, ts))
+    }
+
+    /// variant value from the arguments: `(a, &b)` or with option `named` - ` { a, b: &b }`
+    fn values(&self, named: bool) -> String {
+        if named {
+            let vs: Vec<_> = (self.names.iter().zip(&self.params))
+                .map(|(n, p)| if n == p { n.clone() } else { format!("{n}: {p}") })
+                .collect();
+            format!(" {{ {} }}", vs.join(", "))
+        } else {
+            format!("({})", self.params.join(", "))
+        }
+    }
+
+    /// `values()` as tokens of the argument names and patterns of the signature
+    fn values_ts(&self, named: bool) -> TokenTree {
+        let mut ts = TokenStream::new();
+        for (((id, ..), p), param) in self.arg_tys.iter().zip(&self.params).zip(&self.param_ts) {
+            if named && *p != id.to_string() {
+                ts.extend([Ident(id.clone()), Punct(Pn::new(':', Spacing::Alone))]);
+            }
+            ts.extend(param.iter().cloned());
+            ts.extend(once(Punct(Pn::new(',', Spacing::Alone))));
+        }
+        Group(Gr::new(if named { Brace } else { Delimiter::Parenthesis }, ts))
+    }
+
+    /// variant pattern or expression: `(..)` | ` { .. }` for an empty `field`,
+    /// otherwise the fields by `field` with `{}` replaced by the field index:
+    /// `("ref _{}")` -> `(ref _0, ref _1)` | ` { a: ref _0, b: ref _1 }`
+    fn pattern(&self, named: bool, field: &str) -> String {
+        let fs = (0..self.names.len()).map(|i| field.replace("{}", &i.to_string()));
+        match (named, field.is_empty()) {
+            (false, true) => "(..)".to_string(),
+            (true, true) => " { .. }".to_string(),
+            (false, false) => format!("({})", fs.collect::<Vec<_>>().join(", ")),
+            (true, false) => {
+                let fs = self.names.iter().zip(fs).map(|(n, f)| n.clone() + ": " + &f);
+                format!(" {{ {} }}", fs.collect::<Vec<_>>().join(", "))
+            }
+        }
+    }
+
+    /// the receiver is `&self` or `&mut self` (also in the form `self: &Self`)
+    fn self_ref(&self) -> bool {
+        receiver(self.args.clone()).0.starts_with('&')
+    }
+
+    /// replaces the return type in the signature with `wrap(return type)`
+    fn wrap_out(&mut self, wrap: &str) {
+        let mut it = std::mem::take(&mut self.prev_ts).into_iter();
+        self.prev_ts.extend(it.by_ref().take(self.out_at));
+        let out_len = self.out.clone().into_iter().count();
+        self.prev_ts.extend(TokenStream::from_str(wrap).unwrap());
+        self.prev_ts.extend(it.skip(out_len));
+    }
+
+    /// `#[cfg(..)]` of the signature for the variants and the match arms generated for the method
+    fn cfg(&self) -> String {
+        (self.attrs.iter())
+            .filter(|gr| attr_is(gr, "cfg"))
+            .map(|gr| format!("#[{}] ", gr.stream()))
+            .collect()
+    }
+
+    /// `cfg()` with `#[allow(deprecated)]` for the uses of the variant with `#[deprecated]`
+    fn arm_attrs(&self) -> String {
+        match self.deprecated() {
+            true => self.cfg() + "#[allow(deprecated)] ",
+            false => self.cfg(),
+        }
+    }
+
+    fn deprecated(&self) -> bool {
+        self.attrs.iter().any(|gr| attr_is(gr, "deprecated"))
+    }
+
+    /// doc comments, `#[cfg(..)]` and `#[deprecated]` of the signature for its variant
+    fn variant_attrs(&self) -> TokenStream {
+        let fwd = |gr: &&Gr| ["doc", "cfg", "deprecated"].iter().any(|a| attr_is(gr, a));
+        (self.attrs.iter().filter(fwd))
+            .flat_map(|gr| [Punct(Pn::new('#', Spacing::Alone)), Group(gr.clone())])
+            .collect()
+    }
+
+    /// the binding of the result of the handler for the body: the binding of the tag `#[bind(..)]`
+    /// with its span, otherwise `name` in the context of the signature
+    fn bind_ts(&self, name: &str, span: Span) -> TokenStream {
+        match &self.bind {
+            Some(bind) => TokenStream::from(Ident(bind.clone())),
+            None => spanned_as(name, span),
+        }
+    }
+
+    /// the attributes and the tag before a skipped item do not belong to the next signature
+    fn skip_item(&mut self) {
+        self.attrs.clear();
+        self.to = None;
+        self.bind = None;
+        self.is_async = false;
+    }
+
+    fn prev_extend(&mut self, tt: TokenTree, new_st: ParseStates) -> ParseStates {
         self.prev_ts.extend(once(tt));
         new_st
     }
 
-    fn vec(iit: &mut IntoIter, attr: &Attr) -> Vec<Meth> {
+    fn push_to(mut self, methods: &mut Vec<Meth>, elided: &str) -> Meth {
+        self.err = self.fin(elided).err();
+        methods.push(self);
+        Meth::default()
+    }
+
+    /// the method signatures up to the first handler, its name, the group of its arguments
+    /// and whether it is `async`
+    fn vec(iit: &mut IntoIter, attr: &Attr) -> (Vec<Meth>, Option<(String, Gr, bool)>) {
         let mut methods: Vec<Meth> = Vec::new();
+        let mut handler = None;
+        let handlers = attr.handlers();
         let mut m = Meth::default();
         let mut state = Start;
-        for tt in iit {
+        let mut lg = 0; // depth of `<..>` in generic parameters
+        let mut dash = false; // previous token is `-` (for `->` in generic parameters)
+        while let Some(tt) = iit.next() {
             state = match (state, tt) {
+                (Start, Punct(p)) if p.as_char() == '#' => match iit.clone().next() {
+                    Some(Group(gr)) if gr.delimiter() == Delimiter::Bracket => {
+                        iit.next();
+                        match (tag(&gr, "to"), tag(&gr, "bind")) {
+                            (Some(to), _) => m.to = Some(to),
+                            (_, Some(bind)) => m.bind = Some(bind),
+                            _ => {
+                                m.attrs.push(gr.clone());
+                                m.prev_ts.extend([Punct(p), Group(gr)]);
+                            }
+                        }
+                        Start
+                    }
+                    _ => m.prev_extend(Punct(p), Start),
+                },
                 (Start, Ident(id)) if id.to_string() == "pub" => {
                     m.vis.extend(once(Ident(id.clone())));
                     m.prev_extend(Ident(id), Vis)
@@ -146,55 +942,640 @@ impl Meth {
                     m.vis.extend(once(Group(gr.clone())));
                     m.prev_extend(Group(gr), Vis)
                 }
+                (st @ (Start | Vis), Ident(id)) if id.to_string() == "async" => {
+                    if let Start = st {
+                        m.vis = TokenStream::new()
+                    };
+                    m.is_async = true;
+                    m.prev_extend(Ident(id), Vis)
+                }
                 (st @ (Start | Vis), Ident(id)) if id.to_string() == "fn" => {
                     if let Start = st {
                         m.vis = TokenStream::new()
                     };
+                    m.generics = TokenStream::new();
+                    m.wh = TokenStream::new();
                     m.prev_extend(Ident(id), Name)
                 }
                 (Name, Ident(id)) => {
                     m.prev_ts.extend(once(Ident(id.clone())));
-                    if id.to_string() == attr.run_method {
+                    if handlers.contains(&id.to_string()) {
+                        // the arguments of the handler after its generic parameters
+                        for tt in iit.by_ref() {
+                            m.prev_ts.extend(once(tt.clone()));
+                            if let Group(gr) = tt {
+                                if gr.delimiter() == Delimiter::Parenthesis {
+                                    handler = Some((id.to_string(), gr, m.is_async));
+                                    break;
+                                }
+                            }
+                        }
                         break;
                     }
                     m.ident = Some(id);
                     Args
                 }
+                (Args, Punct(p)) if p.as_char() == '<' && m.generics.is_empty() => {
+                    lg = 1;
+                    m.prev_extend(Punct(p), Lt)
+                }
+                (Lt, Punct(p)) if p.as_char() == '>' && lg == 1 && !dash => {
+                    lg = 0;
+                    m.prev_extend(Punct(p), Args)
+                }
+                (Lt, tt) => {
+                    match &tt {
+                        Punct(p) if p.as_char() == '<' => lg += 1,
+                        Punct(p) if p.as_char() == '>' && !dash => lg -= 1,
+                        _ => (),
+                    }
+                    dash = is_punct(&tt, '-');
+                    m.generics.extend(once(tt.clone()));
+                    m.prev_extend(tt, Lt)
+                }
                 (Args, Group(gr)) if gr.delimiter() == Delimiter::Parenthesis => m.args_parsing(gr),
                 (Minus, Punct(p)) if p.as_char() == '-' => m.prev_extend(Punct(p), Gt),
                 (Gt, Punct(p)) if p.as_char() == '>' => {
                     m.out_span = Some(p.span());
+                    m.out_at = m.prev_ts.clone().into_iter().count() + 1;
                     m.prev_extend(Punct(p), Out)
                 }
-                (Out, Group(gr)) if gr.delimiter() == Brace && attr.out_ident.is_none() => {
+                (Out, Group(gr))
+                    if gr.delimiter() == Brace
+                        && attr.out_ident.is_none()
+                        && attr.bind.is_none()
+                        && m.bind.is_none() =>
+                {
+                    m.skip_item();
                     m.prev_extend(Group(gr), Start) // skip fn with body
                 }
-                (Minus, Group(gr)) if gr.delimiter() == Brace => m.prev_extend(Group(gr), Start),
-                (Out, Ident(id)) if id.to_string() == "where" => m.prev_extend(Ident(id), Start),
-                (Minus | Out, Punct(p)) if p.as_char() == ';' => {
-                    methods.push(m);
-                    m = Meth::default();
+                (Minus, Group(gr)) if gr.delimiter() == Brace => {
+                    m.skip_item();
+                    m.prev_extend(Group(gr), Start)
+                }
+                (Minus | Out, Ident(id)) if id.to_string() == "where" => {
+                    m.prev_extend(Ident(id), Where)
+                }
+                (Where, Group(gr))
+                    if gr.delimiter() == Brace
+                        && (m.out_span.is_none()
+                            || (attr.out_ident.is_none()
+                                && attr.bind.is_none()
+                                && m.bind.is_none())) =>
+                {
+                    m.skip_item();
+                    m.prev_extend(Group(gr), Start) // skip fn with body
+                }
+                (Minus | Out | Where, Punct(p)) if p.as_char() == ';' => {
+                    m = m.push_to(&mut methods, &attr.elided);
                     Start
                 }
-                (Out, Group(gr)) if gr.delimiter() == Brace => {
+                (Out | Where, Group(gr)) if gr.delimiter() == Brace => {
                     m.body = gr.stream();
-                    methods.push(m);
-                    m = Meth::default();
+                    m = m.push_to(&mut methods, &attr.elided);
                     Start
                 }
                 (Out, tt) => {
-                    m.out.extend(TokenStream::from(tt.clone()));
+                    m.out.extend(once(tt.clone()));
                     m.prev_extend(tt, Out)
                 }
-                (_, tt) => m.prev_extend(tt, Start),
+                (Where, tt) => {
+                    m.wh.extend(once(tt.clone()));
+                    m.prev_extend(tt, Where)
+                }
+                (_, tt) => {
+                    m.skip_item();
+                    m.prev_extend(tt, Start)
+                }
             }
         }
         m.ident = None;
         methods.push(m);
-        methods
+        (methods, handler)
+    }
+}
+
+fn is_ident(tt: &TokenTree, s: &str) -> bool {
+    matches!(tt, Ident(id) if id.to_string() == s)
+}
+
+fn is_punct(tt: &TokenTree, ch: char) -> bool {
+    matches!(tt, Punct(p) if p.as_char() == ch)
+}
+
+/// splits `ts` by the `sep` character outside of `<..>` (`->` is not counted as a closing `>`)
+fn split_top(ts: TokenStream, sep: char) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    let mut lg = 0;
+    let mut dash = false;
+    for tt in ts {
+        let is_dash = is_punct(&tt, '-');
+        match &tt {
+            Punct(p) if p.as_char() == sep && lg == 0 => {
+                parts.push(Vec::new());
+                dash = false;
+                continue;
+            }
+            Punct(p) if p.as_char() == '<' => lg += 1,
+            Punct(p) if p.as_char() == '>' && !dash => lg -= 1,
+            _ => (),
+        }
+        dash = is_dash;
+        parts.last_mut().unwrap().push(tt);
+    }
+    if parts.len() == 1 && parts[0].is_empty() {
+        parts.pop();
+    }
+    parts
+}
+
+/// the position of `:` before the type in a function argument `pattern: Type`
+fn type_colon(arg: &[TokenTree]) -> Option<usize> {
+    (0..arg.len()).find(|&i| match &arg[i] {
+        Punct(p) if p.as_char() == ':' && p.spacing() == Spacing::Alone => {
+            !matches!(i.checked_sub(1).map(|j| &arg[j]), Some(Punct(q)) if q.as_char() == ':')
+        }
+        _ => false,
+    })
+}
+
+/// the expression tokens with the value of an argument pattern: without `mut` of bindings
+/// (`&mut` of reference patterns is kept and rejected by `Meth::fin()`),
+/// `None` for patterns that cannot be rebuilt (with `ref`, `_`, `..`, `@`, `|`, literals);
+/// the bindings of the pattern are added to `binds`
+fn pat_expr(pat: &[TokenTree], binds: &mut Vec<String>) -> Option<Vec<TokenTree>> {
+    let mut expr = Vec::new();
+    for (i, tt) in pat.iter().enumerate() {
+        let next = pat.get(i + 1);
+        match tt {
+            Ident(id) if id.to_string() == "mut" => {
+                if i > 0 && is_punct(&pat[i - 1], '&') {
+                    expr.push(tt.clone());
+                }
+            }
+            // `ref` bindings would be rebuilt as references
+            Ident(id) if ["ref", "_"].contains(&&id.to_string()[..]) => return None,
+            Ident(id) => {
+                // a path segment, a struct name or a field name
+                let named = matches!(next, Some(Punct(p)) if p.as_char() == ':')
+                    || matches!(next, Some(Group(g)) if g.delimiter() != Delimiter::Bracket)
+                    || (i > 1 && is_punct(&pat[i - 1], ':') && is_punct(&pat[i - 2], ':'));
+                if !named {
+                    binds.push(id.to_string());
+                }
+                expr.push(tt.clone());
+            }
+            Punct(p) if [',', ':', '&'].contains(&p.as_char()) => expr.push(tt.clone()),
+            Group(gr) => {
+                let inner: Vec<TokenTree> = gr.stream().into_iter().collect();
+                let mut g =
+                    Gr::new(gr.delimiter(), TokenStream::from_iter(pat_expr(&inner, binds)?));
+                g.set_span(gr.span());
+                expr.push(Group(g));
+            }
+            _ => return None,
+        }
+    }
+    Some(expr)
+}
+
+/// the span of the first `&mut` in the expression of a pattern
+fn mut_ref_pat(expr: &[TokenTree]) -> Option<Span> {
+    expr.iter().enumerate().find_map(|(i, tt)| match tt {
+        Punct(p)
+            if p.as_char() == '&' && matches!(expr.get(i + 1), Some(m) if is_ident(m, "mut")) =>
+        {
+            Some(p.span())
+        }
+        Group(gr) => mut_ref_pat(&gr.stream().into_iter().collect::<Vec<_>>()),
+        _ => None,
+    })
+}
+
+/// `ident` of the tag `[name(ident)]` (after `#`): `#[to(EnumName)]` or `#[bind(name)]`
+fn tag(gr: &Gr, name: &str) -> Option<Idn> {
+    match &gr.stream().into_iter().collect::<Vec<_>>()[..] {
+        [Ident(id), Group(g)]
+            if g.delimiter() == Delimiter::Parenthesis && id.to_string() == name =>
+        {
+            match &g.stream().into_iter().collect::<Vec<_>>()[..] {
+                [Ident(e)] => Some(e.clone()),
+                _ => None,
+            }
+        }
+        _ => None,
     }
 }
 
+/// the attribute `[name..]` (after `#`)
+fn attr_is(gr: &Gr, name: &str) -> bool {
+    matches!(gr.stream().into_iter().next(), Some(Ident(id)) if id.to_string() == name)
+}
+
+/// the arguments of the handler `fn name(..)` in the rest of the impl block and whether it is `async`
+fn find_handler(block: IntoIter, name: &str) -> Option<(Gr, bool)> {
+    let tts: Vec<TokenTree> = block.collect();
+    let i = tts.windows(2).position(|w| is_ident(&w[0], "fn") && is_ident(&w[1], name))?;
+    let is_async = i > 0 && is_ident(&tts[i - 1], "async");
+    tts[i..].iter().find_map(|tt| match tt {
+        Group(gr) if gr.delimiter() == Delimiter::Parenthesis => Some((gr.clone(), is_async)),
+        _ => None,
+    })
+}
+
+/// the receiver form of the first argument in `args`: `self`, `&self`, `&mut self`
+/// or `self: Type` (`mut` and lifetimes are omitted), and its span
+fn receiver(args: TokenStream) -> (String, Span) {
+    let slf = split_top(args, ',').into_iter().next().unwrap_or_default();
+    let span = slf.first().map_or(Span::call_site(), |tt| tt.span());
+    let Some(i) = slf.iter().position(|tt| is_ident(tt, "self")) else {
+        return (String::new(), span);
+    };
+    let recv = match &slf[i + 1..] {
+        [Punct(p), ty @ ..] if p.as_char() == ':' => {
+            match &erase_lftms(&ty_str_raw(ty)).replace("'_ ", "")[..] {
+                "Self" => "self".to_string(),
+                "&Self" => "&self".to_string(),
+                "&mut Self" => "&mut self".to_string(),
+                ty => format!("self: {ty}"),
+            }
+        }
+        _ => match &slf[..i] {
+            [Punct(p), .., Ident(m)] if p.as_char() == '&' && m.to_string() == "mut" => {
+                "&mut self".to_string()
+            }
+            [Punct(p), ..] if p.as_char() == '&' => "&self".to_string(),
+            _ => "self".to_string(),
+        },
+    };
+    (recv, span)
+}
+
+/// checks that the handler arguments are `(receiver, name: EnumName)`, returns the receiver form
+fn handler_args(args: Gr, attr: &Attr) -> Result<String, TokenStream> {
+    let (recv, _) = receiver(args.stream());
+    let parts: Vec<_> =
+        split_top(args.stream(), ',').into_iter().filter(|a| !a.is_empty()).collect();
+    let bad = match &parts[..] {
+        [_, arg] if !recv.is_empty() => match arg.iter().position(|tt| is_punct(tt, ':')) {
+            Some(i) => {
+                let ty = &arg[i + 1..];
+                let name = ty.iter().take_while(|tt| !is_punct(tt, '<')).last();
+                match name {
+                    Some(Ident(id)) if id.to_string() == attr.enum_name => return Ok(recv),
+                    _ => ty.first().map_or(arg[i].span(), |tt| tt.span()),
+                }
+            }
+            None => arg[0].span(),
+        },
+        [_, _, arg, ..] if !recv.is_empty() => arg[0].span(),
+        _ => args.span(),
+    };
+    Err(compile_error(
+        &format!(
+            "#[gen]: the handler `{}()` must take the receiver and the argument of type `{}`: \
+`fn {0}(&mut self, method: {1})`",
+            attr.run_method, attr.enum_name
+        ),
+        bad,
+    ))
+}
+
+/// the auto traits that can be added to a trait object
+const AUTO_TRAITS: [&str; 5] = ["Send", "Sync", "Unpin", "UnwindSafe", "RefUnwindSafe"];
+
+/// the std traits that cannot make a trait object (`PartialEq` and `PartialOrd` - without arguments)
+const NOT_DYN: [&str; 15] = [
+    "Sized",
+    "Clone",
+    "Copy",
+    "Default",
+    "Eq",
+    "Ord",
+    "Hash",
+    "From",
+    "Into",
+    "TryFrom",
+    "TryInto",
+    "FromStr",
+    "ToOwned",
+    "FromIterator",
+    "Extend",
+];
+
+/// the name of the trait of the bound (the last segment of its path)
+/// and whether it has generic arguments `<..>`
+fn trait_name(bound: &[TokenTree]) -> (String, bool) {
+    let (mut lg, mut dash) = (0, false);
+    let mut res = (String::new(), false);
+    for tt in bound {
+        match tt {
+            Punct(p) if p.as_char() == '<' => {
+                res.1 |= lg == 0;
+                lg += 1;
+            }
+            Punct(p) if p.as_char() == '>' && !dash => lg -= 1,
+            Ident(id) if lg == 0 => res = (id.to_string(), false),
+            // the arguments of `Fn(..) -> ..`
+            Group(gr) if lg == 0 && gr.delimiter() == Delimiter::Parenthesis => break,
+            _ => (),
+        }
+        dash = is_punct(tt, '-');
+    }
+    res
+}
+
+/// `true` if the type mentions `impl` or one of the `names`
+fn mentions(ty: &[TokenTree], names: &[&String]) -> bool {
+    ty.iter().any(|tt| match tt {
+        Ident(id) => {
+            let s = id.to_string();
+            s == "impl" || names.contains(&&s)
+        }
+        Group(gr) => mentions(&gr.stream().into_iter().collect::<Vec<_>>(), names),
+        _ => false,
+    })
+}
+
+/// `'a` if the lifetime `'a` is not used in the tokens of the `impl` block,
+/// otherwise the first free of `'__me`, `'__me1`, ..
+fn elided_name(block: TokenStream) -> String {
+    fn collect(ts: TokenStream, names: &mut Vec<String>) {
+        let mut prev_q = false;
+        for tt in ts {
+            match &tt {
+                Ident(id) if prev_q => names.push(format!("'{id}")),
+                Group(gr) => collect(gr.stream(), names),
+                _ => (),
+            }
+            prev_q = is_punct(&tt, '\'');
+        }
+    }
+    let mut names = Vec::new();
+    collect(block, &mut names);
+    (once("'a".to_string()).chain(
+        (0..).map(|i| format!("'__me{}", if i > 0 { i.to_string() } else { String::new() })),
+    ))
+    .find(|n| !names.contains(n))
+    .unwrap()
+}
+
+/// the type tokens as is, but with `elided` after `&` without a lifetime and in place of `'_`,
+/// except for the parameters and the result of `fn(..)` and `Fn*(..)` types,
+/// where the elided lifetimes are higher-ranked
+fn elided_lftms(ts: TokenStream, elided: &str) -> Vec<TokenTree> {
+    let name = &elided[1..];
+    let mut res: Vec<TokenTree> = Vec::new();
+    let mut it = ts.into_iter().peekable();
+    // the depth of `<..>` in the result type of `fn(..) -> ..`, if there is one
+    let mut fn_out: Option<i32> = None;
+    while let Some(tt) = it.next() {
+        if let Some(lg) = fn_out.as_mut() {
+            match &tt {
+                Punct(p) if p.as_char() == '<' => *lg += 1,
+                Punct(p) if p.as_char() == '>' && !res.last().is_some_and(|t| is_punct(t, '-')) => {
+                    *lg -= 1
+                }
+                _ => (),
+            }
+            if *lg < 0 || (*lg == 0 && (is_punct(&tt, ',') || is_punct(&tt, '+'))) {
+                fn_out = None;
+            } else {
+                res.push(tt);
+                continue;
+            }
+        }
+        match tt {
+            Ident(id) if ["fn", "Fn", "FnMut", "FnOnce"].contains(&&id.to_string()[..]) => {
+                res.push(Ident(id));
+                if let Some(Group(gr)) = it.peek() {
+                    if gr.delimiter() == Delimiter::Parenthesis {
+                        res.extend(it.next());
+                        if matches!(it.peek(), Some(tt) if is_punct(tt, '-')) {
+                            fn_out = Some(0);
+                        }
+                    }
+                }
+            }
+            Punct(p) if p.as_char() == '&' => {
+                let span = p.span();
+                res.push(Punct(p));
+                if !matches!(it.peek(), Some(tt) if is_punct(tt, '\'')) {
+                    res.push(Punct(Pn::new('\'', Spacing::Joint)));
+                    res.push(Ident(Idn::new(name, span)));
+                }
+            }
+            Punct(p) if p.as_char() == '\'' => {
+                let span = p.span();
+                res.push(Punct(p));
+                match it.next() {
+                    Some(Ident(id)) if id.to_string() == "_" => {
+                        res.push(Ident(Idn::new(name, span)))
+                    }
+                    tt => res.extend(tt),
+                }
+            }
+            Group(gr) => {
+                let mut g = Gr::new(
+                    gr.delimiter(),
+                    TokenStream::from_iter(elided_lftms(gr.stream(), elided)),
+                );
+                g.set_span(gr.span());
+                res.push(Group(g));
+            }
+            tt => res.push(tt),
+        }
+    }
+    res
+}
+
+/// string of the type tokens with spaces only between words, after `,`, around `+`, after `mut`
+/// and after a lifetime before the referenced type: `&'a [T]`, `&mut (dyn A + B)`
+fn ty_str_raw(tts: &[TokenTree]) -> String {
+    let mut s = String::new();
+    let mut word = false; // the previous token ends with an ident or literal
+    let mut lftm = false; // the previous token is the ident of a lifetime or `mut`
+    for tt in tts {
+        match tt {
+            Ident(id) => {
+                if word {
+                    s.push(' ');
+                }
+                lftm = s.ends_with('\'') || id.to_string() == "mut";
+                s.push_str(&id.to_string());
+                word = true;
+                continue;
+            }
+            Literal(l) => {
+                if word {
+                    s.push(' ');
+                }
+                s.push_str(&l.to_string());
+                word = true;
+            }
+            Punct(p) => {
+                if lftm && "&*(".contains(p.as_char()) {
+                    s.push(' ');
+                }
+                match p.as_char() {
+                    '-' if p.spacing() == Spacing::Joint => s.push_str(" -"),
+                    '>' if s.ends_with(" -") => s.push_str("> "),
+                    ',' => s.push_str(", "),
+                    '+' => s.push_str(" + "),
+                    ch => s.push(ch),
+                }
+                word = false;
+            }
+            Group(gr) => {
+                let (open, close) = match gr.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                if lftm {
+                    s.push(' ');
+                }
+                s.push_str(open);
+                s.push_str(&ty_str_raw(&gr.stream().into_iter().collect::<Vec<_>>()));
+                s.push_str(close);
+                word = false;
+            }
+        }
+        lftm = false;
+    }
+    s
+}
+
+/// lifetimes (except `'static` and higher-ranked ones from `for<..>`) found in the type strings,
+/// `first` first
+fn lftms<'s>(typs: impl Iterator<Item = &'s String>, first: &str) -> Vec<String> {
+    let mut v: Vec<String> = Vec::new();
+    for s in typs {
+        let hr: Vec<&str> = (s.match_indices("for<"))
+            .filter_map(|(i, _)| s[i + 4..].split_once('>'))
+            .flat_map(|(lts, _)| lts.split(',').map(str::trim))
+            .collect();
+        for (i, _) in s.match_indices('\'') {
+            let lt: String = once('\'')
+                .chain(s[i + 1..].chars().take_while(|c| c.is_alphanumeric() || *c == '_'))
+                .collect();
+            if lt != "'static" && !hr.contains(&&lt[..]) && !v.contains(&lt) {
+                v.push(lt);
+            }
+        }
+    }
+    if let Some(i) = v.iter().position(|lt| lt == first) {
+        let a = v.remove(i);
+        v.insert(0, a);
+    }
+    v
+}
+
+/// the type string with `'_` in place of all lifetimes
+fn erase_lftms(ty: &str) -> String {
+    let mut res = String::new();
+    let mut it = ty.chars().peekable();
+    while let Some(c) = it.next() {
+        res.push(c);
+        if c == '\'' {
+            res.push('_');
+            while it.next_if(|c| c.is_alphanumeric() || *c == '_').is_some() {}
+        }
+    }
+    res
+}
+
+/// `true` if `s` contains `word` as a whole identifier
+fn has_word(s: &str, word: &str) -> bool {
+    s.split(|c: char| !c.is_alphanumeric() && c != '_').any(|w| w == word)
+}
+
+/// `ts` with `span` set on all tokens, including those in groups
+fn respan(ts: TokenStream, span: Span) -> TokenStream {
+    ts.into_iter()
+        .map(|mut tt| {
+            if let Group(gr) = &tt {
+                tt = Group(Gr::new(gr.delimiter(), respan(gr.stream(), span)));
+            }
+            tt.set_span(span);
+            tt
+        })
+        .collect()
+}
+
+/// the code `s` located at `span`: with the resolution (and lints) of generated code,
+/// but with diagnostics pointing at `span`
+fn spanned(s: &str, span: Span) -> TokenStream {
+    respan(TokenStream::from_str(s).unwrap(), Span::call_site().located_at(span))
+}
+
+/// the code `s` with all tokens at `span`: resolved as the user's code at `span`
+/// (in method bodies - as the signature, which may come from a `#[gen_part]` macro)
+fn spanned_as(s: &str, span: Span) -> TokenStream {
+    respan(TokenStream::from_str(s).unwrap(), span)
+}
+
+/// the code `s` with the placeholders `$0`, `$1`, .. filled by `args` (string, tokens):
+/// for the doc - with the strings, for the compiler - `spanned(s, span)` with the tokens,
+/// so that the user's types and bounds keep their spans and their errors are reported once
+fn filled(s: &str, span: Span, args: &[(&str, &[TokenTree])]) -> (String, TokenStream) {
+    let mut doc = s.to_string();
+    for (i, (arg, _)) in args.iter().enumerate().rev() {
+        doc = doc.replace(&format!("${i}"), arg);
+    }
+    let tts: Vec<&[TokenTree]> = args.iter().map(|a| a.1).collect();
+    (doc, spanned_with(s, span, &tts))
+}
+
+/// `spanned(s, span)` with the placeholders `$0`, `$1`, .. replaced by the tokens `args[0]`, ..
+fn spanned_with(s: &str, span: Span, args: &[&[TokenTree]]) -> TokenStream {
+    fn fill(ts: TokenStream, args: &[&[TokenTree]]) -> TokenStream {
+        let mut res = Vec::new();
+        let mut it = ts.into_iter();
+        while let Some(tt) = it.next() {
+            match tt {
+                Punct(p) if p.as_char() == '$' => match it.next() {
+                    Some(Literal(n)) => {
+                        res.extend(args[n.to_string().parse::<usize>().unwrap()].iter().cloned())
+                    }
+                    _ => unreachable!(),
+                },
+                Group(gr) => {
+                    let mut g = Gr::new(gr.delimiter(), fill(gr.stream(), args));
+                    g.set_span(gr.span());
+                    res.push(Group(g));
+                }
+                tt => res.push(tt),
+            }
+        }
+        TokenStream::from_iter(res)
+    }
+    fill(spanned(s, span), args)
+}
+
+/// `::core::compile_error!{"msg"}` with all tokens at `span`
+fn compile_error(msg: &str, span: Span) -> TokenStream {
+    let mut lit = Lit::string(msg);
+    lit.set_span(span);
+    TokenStream::from_iter(
+        [
+            Punct(Pn::new(':', Spacing::Joint)),
+            Punct(Pn::new(':', Spacing::Alone)),
+            Ident(Idn::new("core", span)),
+            Punct(Pn::new(':', Spacing::Joint)),
+            Punct(Pn::new(':', Spacing::Alone)),
+            Ident(Idn::new("compile_error", span)),
+            Punct(Pn::new('!', Spacing::Alone)),
+            Group(Gr::new(Brace, TokenStream::from(Literal(lit)))),
+        ]
+        .map(|mut tt| {
+            tt.set_span(span);
+            tt
+        }),
+    )
+}
+
 fn ts_to_doc(ts: &TokenStream) -> String {
     let s = ts.to_string().replace("& ", "&").replace(":: ", "::");
     let inds: Vec<_> = s.match_indices(&['!', '(', ',', ':', '<', '>']).map(|t| t.0).collect();
@@ -211,7 +1592,7 @@ fn ts_to_doc(ts: &TokenStream) -> String {
 /// This allows the handler method to control the behavior of the methods depending on the context.
 ///
 /// #### Macro call syntax
-/// **`#[methods_enum::gen(`*EnumName* `, ` | `: ` *handler_name* ( `, ` | ` = ` *OutName* `!`<sup>?</sup> )<sup>?</sup> `)]`**
+/// **`#[methods_enum::gen(`*EnumName* `, ` | `: ` *handler_name* ( `, ` | ` = ` *OutName* `!`<sup>?</sup> )<sup>?</sup> ( `; ` *options* )<sup>?</sup> `)]`**
 ///
 /// where:
 /// - ***EnumName***: The name of the automatically generated enum.
@@ -220,11 +1601,27 @@ fn ts_to_doc(ts: &TokenStream) -> String {
 /// : The name of an automatically generated enum with variants from the return types.
 ///
 /// Replacing the delimiter **`, `** after *EnumName* with **`: `** or before *OutName* with **` = `**
-/// will automatically add the `#[derive(Debug)]` attribute to the corresponding enum.
+/// will automatically add the `#[derive(Debug)]` attribute to the corresponding enum
+/// (for *EnumName* - also `Display` rendering the call: `add_text("...")`).
 ///
 /// Setting `!` after *OutName* enables checking the returned variant by its name, not by its type.
 ///
-/// The macro attribute is set before an individual (non-Trait) impl block. Based on the method signatures of the impl block, it generates: `enum` with parameters from argument tuples and generates `{}` bodies of these methods with calling the argument handler method from this `enum`.  
+/// In addition to *EnumName*, the fieldless `enum` *EnumName*`Kind` is generated with the constant `ALL` of all its variants and the method `name()`; the name *EnumName*`Kind` is reserved in the module of the impl block. *EnumName* gets the methods `kind()` and `name()`.
+///
+/// ***options*** - a comma-separated list after `;`:
+/// - `named` - the variants of *EnumName* are struct-like with the argument names as fields: `Meth::move_to { x: i32, y: i32 }` instead of `Meth::move_to(i32, i32)`.
+/// - `try` (with *OutName*) - instead of panicking on a mismatched *OutName* variant, methods without a default value return `Result<T, `*OutName*`Mismatch>`.
+/// - `fallback = `*fn_name* (with *OutName*) - instead of panicking on a mismatched *OutName* variant, methods without a default value return `Self::`*fn_name*`("method_name", out)`.
+/// - `owned` - also generates *EnumName*`Owned` with owned types of borrowed arguments, `From<`*EnumName*`>` for it and `apply(self, target: &mut SelfType)` that repeats the method call.
+/// - `serde` (cargo feature `serde`) - *EnumName* (or *EnumName*`Owned` with `owned`) and *OutName* derive `serde::{Serialize, Deserialize}`; implies `named`.
+/// - `trace` (cargo feature `tracing`) - each generated method opens a `tracing` span named after the method with the *EnumName*`Kind` of the call.
+/// - `before = `*hook*, `after = `*hook* - the generated method bodies call `self.`*hook*`(&meth)` before the handler and `self.`*hook*`(`*EnumName*`Kind::method, &result)` after it (the result must not borrow `self` of a handler with `&mut self`).
+/// - `derive(`*Trait*`, ..)`, `attr(`*attribute*`)` - derives and an attribute for *EnumName*; `out_derive(..)`, `out_attr(..)` - the same for *OutName*.
+/// - `parts(`*label*`, ..)` - adds to `enum` the signatures of other impl blocks marked with [`#[methods_enum::gen_part(EnumName, label)]`](attr.gen_part.html).
+/// - `bind = `*name* - the default bodies get the result of the handler in the variable *name* (instead of `_out`, the lowercased *OutName* with `_`); without *OutName*, methods with a return type and a body are also included in `enum`. The tag `#[bind(`*name*`)]` before a signature sets the binding of its body only.
+/// - `to(`*EnumName2*`, `*handler_name2*`)` - the signatures tagged with `#[to(`*EnumName2*`)]` go to their own `enum` *EnumName2* with its own handler (and the same *OutName*); can be repeated.
+///
+/// The macro attribute is set before an individual impl block (inherent or `impl Trait for Type`). Based on the method signatures of the impl block, it generates: `enum` with parameters from argument tuples and generates `{}` bodies of these methods with calling the argument handler method from this `enum`.  
 /// This allows the handler method to control the behavior of methods depending on the context, including structuring enum-matching by state.
 ///
 /// ## Usage example
@@ -291,24 +1688,55 @@ fn ts_to_doc(ts: &TokenStream) -> String {
 ///
 /// The macro duplicates the output for the compiler in the doc-comments.
 /// Therefore, in the IDE[^rust_analyzer], you can always see the declaration of the generated `enum` and the generated method bodies.
-/// 
+///
 /// [^rust_analyzer]: *rust-analyzer may not expand proc-macro when running under nightly or old rust edition.* In this case it is recommended to set in its settings: [`"rust-analyzer.server.extraEnv": { "RUSTUP_TOOLCHAIN": "stable" }`](https://rust-analyzer.github.io/manual.html#toolchain)
 ///
 /// ## Restrictions
-/// 
-/// - The **`#[gen(...)]`** macro does not work on generic methods (including lifetime generics). As a general rule, methods with <...> before the argument list, with `where` before the body, or `impl` in the argument type declaration will be silently ignored for inclusion in `enum`.
-/// - The macro will ignore signatures with destructured arguments.
-/// - The macro ignores also methods with a `mut` prefix in front of a method argument name (except  `self`): move such an argument to a mut variable in the body of the handler method.
-/// - The `self` form of all methods of the same `enum` must be the same and match the `self` form of the handler method. As a rule, it is either `&mut self` everywhere or `self` in methods + `mut self` in the handler method. However, it is allowed to group method signatures into multiple `impl` blocks with different `enum` and handler methods. See example below.
-/// 
+///
+/// - Generic methods are included in `enum` as follows: lifetime parameters of methods become parameters of `enum`, and arguments of types `T`, `&T`, `&mut T` or `impl Trait`, where `T` is a generic type of the method, are erased into `&dyn` of their bounds (from `<...>` and `where`), e.g. `val: T` with `T: Display` gives the variant field `&'a (dyn Display)`. Such bounds must make a trait object: one dyn-compatible trait, plus auto traits (`Send`, `Sync`, `Unpin`) and lifetimes; the macro reports an error on bounds like `Clone`, `Into<String>`, `Display + Debug` and on `?Sized` types. Generic types of the method cannot be used in argument types in other forms.
+/// - Argument types are copied into the variants as is, only elided lifetimes of references and `'_` get the lifetime `'a` of `enum` (`'__me` if the block uses `'a`), but not those in `fn(..)` and `Fn*(..)` types, which are higher-ranked: `f: &dyn Fn(&str) -> &str` gives the variant field `&'a dyn Fn(&str) -> &str`. Lifetimes hidden in paths, such as `Formatter` instead of `Formatter<'_>`, must be written explicitly.
+/// - Signatures can be `async fn` if the handler is `async`, see [Async methods](attr.gen.html#async-methods).
+/// - Attributes and doc comments of signatures are kept on the generated methods; `#[cfg(..)]`, `#[deprecated]` and doc comments are also forwarded to the variants, see [Attributes of signatures](attr.gen.html#attributes-of-signatures).
+/// - Arguments can be `mut name: T` and destructuring patterns of tuples, structs and arrays: `(x, y): (i32, i32)`, `Point { x, y }: Point`. The variant field gets the argument type, the generated body rebuilds the value from the pattern (without `mut`); with the option `named` the field of a pattern is named by its bindings joined with `_` (`x_y`). The macro ignores signatures with patterns that cannot be rebuilt: with `ref`, `_`, `..`, `@`, `|` or literals. Reference patterns `&mut ..` are reported as an error: their bindings are copies of the referenced value.
+/// - The `self` form of all methods of the same `enum` must be the same and match the `self` form of the handler method, otherwise the macro reports an error on the method. As a rule, it is either `&mut self` everywhere or `self` in methods + `mut self` in the handler method. Typed receivers are also supported: `self: Box<Self>`, `self: Rc<Self>`, `self: Pin<&mut Self>` (`self: &Self` is the same as `&self`). However, it is allowed to route method signatures to different `enum` and handler methods: with the option `to(..)` in one `impl` block or with multiple `impl` blocks. See example below.
+///
 /// ## [gen macro details and use cases](attr.gen.html#gen-macro-details-and-use-cases)
 ///
 #[doc = include_str!("gen_details.md")]
 #[proc_macro_attribute]
 pub fn gen(attr_ts: TokenStream, item_ts: TokenStream) -> TokenStream {
+    gen_impl(attr_ts, item_ts, None)
+}
+
+/// `#[gen]` for the impl block `item_ts`, with `part_sigs` - the signatures from the `#[gen_part]`
+/// blocks, if the `parts(..)` option is already processed
+fn gen_impl(
+    attr_ts: TokenStream,
+    item_ts: TokenStream,
+    part_sigs: Option<TokenStream>,
+) -> TokenStream {
     // std::fs::write("target/debug/item_ts.log", format!("{}\n\n{0:#?}", item_ts)).unwrap();
 
-    let attr = Attr::new(attr_ts);
+    let item_copy = item_ts.clone();
+    let mut attr = match Attr::new(attr_ts.clone()) {
+        Ok(attr) => attr,
+        Err(err) => return with_stubs(item_copy, err),
+    };
+    if let (None, Some((first, rest))) = (&part_sigs, attr.parts.split_first()) {
+        // the macros of the parts add their signatures in turn and call `gen_parts!`
+        let rest: Vec<TokenTree> = rest.join(&Punct(Pn::new(',', Spacing::Alone)));
+        let mut res_ts = TokenStream::from_iter(first.iter().cloned());
+        res_ts.extend(once(Punct(Pn::new('!', Spacing::Alone))));
+        res_ts.extend(once(Group(Gr::new(
+            Brace,
+            TokenStream::from_iter([
+                Group(Gr::new(Delimiter::Bracket, TokenStream::from_iter(rest))),
+                Group(Gr::new(Delimiter::Parenthesis, attr_ts)),
+                Group(Gr::new(Brace, item_ts)),
+            ]),
+        ))));
+        return res_ts;
+    }
 
     let mut item_it = item_ts.into_iter();
 
@@ -317,170 +1745,571 @@ pub fn gen(attr_ts: TokenStream, item_ts: TokenStream) -> TokenStream {
     );
     item_ts.extend(once(Ident(Idn::new("impl", Span::call_site()))));
 
-    let mut block_it = match [item_it.next(), item_it.next(), item_it.next()] {
-        [Some(Ident(item_n)), Some(Group(gr)), None] if gr.delimiter() == Brace => {
-            item_ts.extend(once(Ident(item_n)));
-            gr.stream().into_iter()
+    let mut header: Vec<TokenTree> = Vec::new();
+    let block = loop {
+        match item_it.next() {
+            Some(Group(gr)) if gr.delimiter() == Brace => match &part_sigs {
+                Some(sigs) => {
+                    break TokenStream::from_iter(sigs.clone().into_iter().chain(gr.stream()))
+                }
+                None => break gr.stream(),
+            },
+            Some(tt) => header.push(tt),
+            None => {
+                let span =
+                    item_copy.clone().into_iter().next().map_or(Span::call_site(), |tt| tt.span());
+                let err =
+                    compile_error("#[gen]: attribute #[gen] must be set on an `impl` block", span);
+                return with_stubs(item_copy, err);
+            }
         }
-        m => panic!(
-            "#[gen]: SYNTAX ERROR 
-'attribute #[gen] must be set on block impl without treyds and generics': {m:?}"
-        ),
     };
+    item_ts.extend(header.iter().cloned());
+    let head = ImplHead::new(&header);
+    if let (Some(tr), Some(try_id)) = (&head.trait_ty, &attr.try_out) {
+        let err = compile_error(
+            &format!(
+                "#[gen]: option `try` changes the return types of the methods to `Result<..>`, \
+which must match the signatures of trait `{tr}`: use the option `fallback = fn_name` instead"
+            ),
+            try_id.span(),
+        );
+        return with_stubs(item_copy, err);
+    }
+    attr.elided = elided_name(TokenStream::from_iter(header.iter().cloned().chain(block.clone())));
+
+    let fn_count = block.clone().into_iter().filter(|tt| is_ident(tt, "fn")).count();
+    let mut block_it = block.into_iter();
+    let (mut methods, handler) = Meth::vec(&mut block_it, &attr);
+    // *EnumName* and the enums of the option `to(..)`
+    let groups: Vec<Attr> = (0..=attr.to.len()).map(|i| attr.group(i)).collect();
+    for m in methods.iter_mut() {
+        let Some(tag) = &m.to else { continue };
+        match groups.iter().position(|g| g.enum_name == tag.to_string()) {
+            Some(i) => m.group = i,
+            None if m.err.is_none() => {
+                m.err = Some(compile_error(
+                    &format!(
+                        "#[gen]: `enum {tag}` of the tag `#[to({tag})]` is not declared \
+in the attribute: expected the option `to({tag}, handler_name)`"
+                    ),
+                    tag.span(),
+                ))
+            }
+            None => (),
+        }
+    }
+    // the receiver form and `async` for all methods of each enum and where they come from
+    let mut base_recvs = Vec::new();
+    for (i, attr) in groups.iter().enumerate() {
+        let args = match &handler {
+            Some((name, gr, is_async)) if name == &attr.run_method => Some((gr.clone(), *is_async)),
+            _ => find_handler(block_it.clone(), &attr.run_method),
+        };
+        base_recvs.push(match args {
+            Some((gr, is_async)) => match handler_args(gr, attr) {
+                Ok(recv) => Some((recv, format!("the handler `{}()`", attr.run_method), is_async)),
+                Err(err) => return with_stubs(item_copy, err),
+            },
+            // the handler is in another impl block: for a trait or with signatures only
+            None if head.trait_ty.is_some()
+                || fn_count == methods.iter().filter(|m| m.ident.is_some()).count() =>
+            {
+                (methods.iter().filter(|m| m.group == i && m.err.is_none()))
+                    .find_map(|m| m.ident.as_ref().map(|id| (m, id)))
+                    .map(|(m, id)| {
+                        (receiver(m.args.clone()).0, format!("method `{id}()`"), m.is_async)
+                    })
+            }
+            None => {
+                let err = compile_error(
+                    &format!(
+                        "#[gen]: the handler method `{}()` is not found in the impl block: \
+the signatures for `enum {}` are read up to it \
+(without the handler, the impl block can contain only these signatures)",
+                        attr.run_method, attr.enum_name
+                    ),
+                    attr.run_ident.as_ref().unwrap().span(),
+                );
+                return with_stubs(item_copy, err);
+            }
+        });
+    }
+    // the return type borrows `self`: it has the elided lifetime
+    let borrows_self = |m: &Meth| {
+        let out = ty_str_raw(&elided_lftms(m.out.clone(), &attr.elided));
+        lftms(once(&out), "").contains(&attr.elided)
+    };
+    // the method that makes `enum OutName` borrow `self`
+    let out_borrower = (attr.out_ident.as_ref())
+        .and_then(|_| methods.iter().filter(|m| m.err.is_none()).find(|m| borrows_self(m)))
+        .and_then(|m| m.ident.as_ref().map(|id| id.to_string()));
+    for m in methods.iter_mut() {
+        m.typ_ts = m.typ_ts.iter().map(|t| head.unself(t)).collect();
+        m.typs = m.typ_ts.iter().map(|t| ty_str_raw(t)).collect();
+        let (attr, base_recv) = (&groups[m.group], &base_recvs[m.group]);
+        if let (Some((base, from, is_async)), Some(ident), None) = (base_recv, &m.ident, &m.err) {
+            let (recv, span) = receiver(m.args.clone());
+            if &recv != base {
+                m.err = Some(compile_error(
+                    &format!(
+                        "#[gen]: the receiver `{recv}` of method `{ident}()` does not match \
+the receiver `{base}` of {from}: all methods of `enum {}` must have the same receiver",
+                        attr.enum_name
+                    ),
+                    span,
+                ));
+            } else if m.is_async != *is_async {
+                let not = |a: bool| if a { "" } else { "not " };
+                m.err = Some(compile_error(
+                    &format!(
+                        "#[gen]: method `{ident}()` is {}`async`, but {from} is {}`async`: \
+the methods of `enum {}` must be `async` together with the handler",
+                        not(m.is_async),
+                        not(*is_async),
+                        attr.enum_name
+                    ),
+                    ident.span(),
+                ));
+            }
+        }
+        if let (Some(after), Some(ident), None) = (&attr.after, &m.ident, &m.err) {
+            if !m.self_ref() {
+                m.err = Some(compile_error(
+                    &format!(
+                        "#[gen]: option `after = {after}` requires `&self` or `&mut self` \
+in method `{ident}()`: `self` is moved into the handler"
+                    ),
+                    ident.span(),
+                ));
+            } else if base_recv.as_ref().is_some_and(|(base, ..)| base == "&mut self") {
+                let borrower = match (&attr.out_ident, &out_borrower) {
+                    (Some(o), Some(n)) => {
+                        Some(format!("`enum {o}` borrows `self` (method `{n}()`)"))
+                    }
+                    (None, _) if borrows_self(m) => Some("the result borrows `self`".to_string()),
+                    _ => None,
+                };
+                if let Some(borrower) = borrower {
+                    m.err = Some(compile_error(
+                        &format!(
+                            "#[gen]: option `after = {after}` cannot be used with method `{ident}()`: \
+`self.{after}(..)` is called while the result of the handler with `&mut self` is alive, \
+and {borrower}"
+                        ),
+                        ident.span(),
+                    ));
+                }
+            }
+        }
+    }
 
-    let methods = Meth::vec(&mut block_it, &attr);
-
-    let head = r##"
-        #[derive(Debug)]
-        #[allow(non_camel_case_types)]
-        /// Formed by macro [`#[methods_enum::gen(...)]`](https://docs.rs/methods-enum):
-        /// ```
-        /// #[derive(Debug)]
-        /// #[allow(non_camel_case_types)]
-        #[doc = "enum "##;
-    let head_w_o_dbg = head.lines().filter(|s| !s.ends_with("g)]")).collect::<Vec<_>>().join("\n");
-    //                 (name.0, out.1, span.2)
-    let mut outs: Vec<(String, String, Span)> = Vec::new();
-    let mut enum_doc = " {".to_string();
-    let mut enum_ts = TokenStream::new();
+    //                 (name.0, out.1, span.2, type.3, cfg.4, type tokens.5)
+    let mut outs: Vec<(String, String, Span, String, String, Vec<TokenTree>)> = Vec::new();
     for m in methods.iter() {
-        if let Some(ident) = &m.ident {
-            enum_ts.extend(once(Ident(ident.clone())));
-            let typs = m.typs.replace('&', "&'a ");
-            enum_ts.extend(TokenStream::from_str(&format!("({typs}), ")));
-            enum_doc.push_str(&format!("\n    {ident}({typs}), "));
-            if let Some(out_span) = m.out_span {
-                outs.push((ident.to_string(), ts_to_doc(&m.out), out_span));
-            }
+        if let (Some(ident), Some(out_span), None) = (&m.ident, m.out_span, &m.err) {
+            let out_ts = head.unself(&elided_lftms(m.out.clone(), &attr.elided));
+            let out = ty_str_raw(&out_ts);
+            outs.push((ident.to_string(), ts_to_doc(&m.out), out_span, out, m.cfg(), out_ts));
         }
     }
-    let lftm = if enum_doc.contains('&') { "<'a>" } else { "" };
-    enum_doc.push_str("\n}\n```\n---\nMethod bodies generated by the same macro:\n```");
+    let mismatch = attr.out_ident.as_ref().map(|o| format!("{o}Mismatch")).unwrap_or_default();
+    let mut bodies: Vec<TokenStream> = methods.iter().map(|_| TokenStream::new()).collect();
+    let mut res_ts = TokenStream::new();
+    for (gi, attr) in groups.iter().enumerate() {
+        let sigs: Vec<&Meth> = methods.iter().filter(|m| m.group == gi).collect();
+        let mut enum_doc = " {".to_string();
+        let mut enum_ts = TokenStream::new();
+        //                 (name.0, pattern.1, binding pattern.2, number of fields.3, cfg.4)
+        let mut kinds: Vec<(String, String, String, usize, String)> = Vec::new();
+        for m in sigs.iter() {
+            if let (Some(ident), None) = (&m.ident, &m.err) {
+                kinds.push((
+                    ident.to_string(),
+                    m.pattern(attr.named, ""),
+                    m.pattern(attr.named, "ref _{}"),
+                    m.names.len(),
+                    m.arm_attrs(),
+                ));
+                enum_ts.extend(m.variant_attrs());
+                enum_ts.extend(once(Ident(ident.clone())));
+                enum_ts.extend(once(m.fields_ts(attr.named)));
+                enum_ts.extend(once(Punct(Pn::new(',', Spacing::Alone))));
+                let fields = m.fields(attr.named);
+                let var_attrs: String = (m.attrs.iter())
+                    .filter(|gr| attr_is(gr, "cfg") || attr_is(gr, "deprecated"))
+                    .map(|gr| format!("\n    #[{}]", ts_to_doc(&gr.stream())))
+                    .collect();
+                enum_doc.push_str(&format!("{var_attrs}\n    {ident}{fields}, "));
+            }
+        }
+        let gens = head.generics(sigs.iter().flat_map(|m| &m.typs), &attr.elided);
+        let kind_ts = kind_enum(attr, &kinds, &gens, &sigs);
+        let owned_ts = match attr.owned {
+            true => owned_enum(attr, &head, &sigs, &gens),
+            false => TokenStream::new(),
+        };
+        let mut derives = attr.derives.clone();
+        if !attr.owned {
+            derives.extend(attr.serde_derives());
+        }
+        let variants = || sigs.iter().filter(|m| m.ident.is_some() && m.err.is_none());
+        let clones: Vec<_> = (variants().zip(&kinds))
+            .map(|(m, k)| {
+                (
+                    k.0.clone(),
+                    k.2.clone(),
+                    m.pattern(attr.named, "::core::clone::Clone::clone(_{})"),
+                    k.4.clone(),
+                )
+            })
+            .collect();
+        let typs: Vec<(&String, &[TokenTree])> =
+            (variants()).flat_map(|m| m.typs.iter().zip(m.typ_ts.iter().map(|t| &t[..]))).collect();
+        let (clone_s, clone_ts) = clone_impls(&mut derives, &attr.enum_name, &gens, &clones, &typs);
+        enum_doc.push_str("\n}\n```\n---\nMethod bodies generated by the same macro:\n```");
 
-    let is_result = attr.out_ident.is_none() && outs.iter().any(|t| t.1.contains("Result<"));
-    let self_run_enum = format!("self.{}({}::", attr.run_method, attr.enum_name);
-    let mut methods_ts = TokenStream::new();
-    for m in methods {
-        methods_ts.extend(m.prev_ts);
-        if let Some(ident) = m.ident {
-            enum_doc.push_str(&format!(
-                "\n{}fn {ident}({})",
-                (ts_to_doc(&m.vis) + " ").trim_start(),
-                ts_to_doc(&m.args)
-            ));
-            let mut body_ts = TokenStream::new();
-            let out = if m.out.is_empty() {
-                enum_doc.push_str(" {");
-                if is_result {
-                    enum_doc.push_str("\n    #![allow(unused_must_use)]");
-                    body_ts.extend(TokenStream::from_str("#![allow(unused_must_use)]").unwrap());
-                }
-                String::new()
-            } else {
-                let name = ident.to_string();
-                let find_out = outs.iter().find(|t| t.0 == name).unwrap().1.clone();
-                enum_doc.push_str(&format!(" -> {find_out} {{"));
-                find_out
-            };
-            let call_run = format!("{self_run_enum}{ident}({}))", m.params);
-            if attr.out_ident.is_none() || m.out.is_empty() {
-                enum_doc.push_str(&format!("\n    {call_run}"));
-                body_ts.extend(TokenStream::from_str(&call_run).unwrap());
-                if m.out.is_empty() {
-                    enum_doc.push_str(";");
-                    body_ts.extend(once(Punct(Pn::new(';', Spacing::Alone))));
-                }
-            } else if let Some(out_ident) = &attr.out_ident {
-                enum_doc.push_str(&format!("\n    match {call_run} {{"));
-                body_ts.extend(TokenStream::from_str(&format!("match {call_run}")).unwrap());
-                let out_enum = out_ident.to_string() + "::";
-                let varname = format!("_{}", out_ident).to_lowercase();
-                let lside = if attr.strict_types {
-                    format!("{out_enum}{ident}(x)")
+        let is_result = attr.out_ident.is_none()
+            && (variants()).any(|m| {
+                m.out_span.is_some() && m.body.is_empty() && ts_to_doc(&m.out).contains("Result<")
+            });
+        let self_run_enum = format!("self.{}({}::", attr.run_method, attr.enum_name);
+        let try_out = |m: &Meth| {
+            attr.try_out.is_some() && m.err.is_none() && !m.out.is_empty() && m.body.is_empty()
+        };
+        for (m, methods_ts) in methods.iter_mut().zip(&mut bodies).filter(|(m, _)| m.group == gi) {
+            let values = m.values(attr.named);
+            let is_try = try_out(m);
+            if is_try {
+                m.wrap_out(&format!("Result<{}, {mismatch}>", m.out));
+            }
+            if let Some(err) = m.err.clone() {
+                methods_ts.extend(once(Group(Gr::new(Brace, err))));
+            } else if let Some(ident) = m.ident.clone() {
+                enum_doc.push_str(&format!(
+                    "\n{}{}fn {ident}{}({})",
+                    (ts_to_doc(&m.vis) + " ").trim_start(),
+                    if m.is_async { "async " } else { "" },
+                    if m.generics.is_empty() {
+                        String::new()
+                    } else {
+                        format!("<{}>", ts_to_doc(&m.generics))
+                    },
+                    ts_to_doc(&m.args)
+                ));
+                let mut body_ts = TokenStream::new();
+                let span = ident.span();
+                let out = if m.out.is_empty() {
+                    if !m.wh.is_empty() {
+                        enum_doc.push_str(&format!("\nwhere\n    {}", ts_to_doc(&m.wh)));
+                    }
+                    enum_doc.push_str(" {");
+                    if is_result {
+                        enum_doc.push_str("\n    #![allow(unused_must_use)]");
+                        body_ts.extend(spanned_as("#![allow(unused_must_use)]", span));
+                    }
+                    String::new()
                 } else {
-                    (outs.iter())
-                        .filter_map(|(n, o, _)| (o == &out).then(|| out_enum.clone() + n + "(x)"))
-                        .reduce(|s, n| s + " | " + &n)
-                        .unwrap()
+                    let name = ident.to_string();
+                    let find_out = outs.iter().find(|t| t.0 == name).unwrap().1.clone();
+                    if is_try {
+                        enum_doc.push_str(&format!(" -> Result<{find_out}, {mismatch}>"));
+                    } else {
+                        enum_doc.push_str(&format!(" -> {find_out}"));
+                    }
+                    if !m.wh.is_empty() {
+                        enum_doc.push_str(&format!("\nwhere\n    {}", ts_to_doc(&m.wh)));
+                    }
+                    enum_doc.push_str(" {");
+                    find_out
+                };
+                if m.deprecated() {
+                    enum_doc.push_str("\n    #![allow(deprecated)]");
+                    body_ts.extend(spanned_as("#![allow(deprecated)]", span));
+                }
+                if m.mut_arg {
+                    // the arguments are moved into the variant
+                    enum_doc.push_str("\n    #![allow(unused_mut)]");
+                    body_ts.extend(spanned_as("#![allow(unused_mut)]", span));
+                }
+                let mut call_run = format!("{self_run_enum}{ident}{values})");
+                // the variant keeps the tokens of the method name and the arguments
+                let mut variant_ts = spanned_as(&format!("{}::", attr.enum_name), span);
+                variant_ts.extend([Ident(ident.clone()), m.values_ts(attr.named)]);
+                let mut call_ts = spanned_as(&format!("self.{}", attr.run_method), span);
+                call_ts.extend(once(Group(Gr::new(Delimiter::Parenthesis, variant_ts.clone()))));
+                let mut hooks: Vec<(String, TokenStream)> = Vec::new();
+                let span_s = format!(
+                    "::tracing::debug_span!(\"{ident}\", variant = ?{}Kind::{ident})",
+                    attr.enum_name
+                );
+                let hook = |s: String| {
+                    let ts = spanned_as(&s, span);
+                    (s, ts)
                 };
-                enum_doc.push_str(&format!("\n        {lside} => x,\n        {varname} => "));
-                let mut match_ts =
-                    TokenStream::from_str(&format!("{lside} => x, {varname} => ")).unwrap();
-                if m.body.is_empty() {
-                    let panic_s = format!(
-                        "panic!(\"Type mismatch in the {ident}() method:
+                let semi = || Punct(Pn::new(';', Spacing::Alone));
+                if attr.trace && !m.is_async {
+                    hooks.push(hook(format!("let _span = {span_s}.entered();")));
+                }
+                if let Some(before) = &attr.before {
+                    let mut meth_ts = spanned_as("let _meth =", span);
+                    meth_ts.extend(variant_ts);
+                    meth_ts.extend(once(semi()));
+                    hooks.push((
+                        format!("let _meth = {}::{ident}{values};", attr.enum_name),
+                        meth_ts,
+                    ));
+                    hooks.push(hook(format!("self.{before}(&_meth);")));
+                    call_run = format!("self.{}(_meth)", attr.run_method);
+                    call_ts = spanned_as(&call_run, span);
+                }
+                if m.is_async && attr.trace {
+                    // the entered span must not be held across `.await`
+                    call_run =
+                        format!("::tracing::Instrument::instrument({call_run}, {span_s}).await");
+                    call_ts.extend(spanned_as(&format!(", {span_s}"), span));
+                    let mut instr_ts = spanned_as("::tracing::Instrument::instrument", span);
+                    instr_ts.extend(once(Group(Gr::new(Delimiter::Parenthesis, call_ts))));
+                    instr_ts.extend(spanned_as(".await", span));
+                    call_ts = instr_ts;
+                } else if m.is_async {
+                    call_run.push_str(".await");
+                    call_ts.extend(spanned_as(".await", span));
+                }
+                if let Some(after) = &attr.after {
+                    let mut res_ts = spanned_as("let _res =", span);
+                    res_ts.extend(call_ts);
+                    res_ts.extend(once(semi()));
+                    hooks.push((format!("let _res = {call_run};"), res_ts));
+                    hooks.push(hook(format!(
+                        "self.{after}({}Kind::{ident}, &_res);",
+                        attr.enum_name
+                    )));
+                    call_run = "_res".to_string();
+                    call_ts = spanned_as(&call_run, span);
+                }
+                for (hook, hook_ts) in hooks {
+                    enum_doc.push_str(&format!("\n    {hook}"));
+                    body_ts.extend(hook_ts);
+                }
+                if attr.after.is_some() && m.out.is_empty() {
+                    // the result is already in `_res`
+                } else if let (None, Some(bind), false) =
+                    (&attr.out_ident, m.bind.as_ref().or(attr.bind.as_ref()), m.body.is_empty())
+                {
+                    enum_doc.push_str(&format!(
+                        "\n    let {bind} = {call_run};\n    {}",
+                        ts_to_doc(&m.body)
+                    ));
+                    body_ts.extend(spanned_as("let", span));
+                    body_ts.extend(m.bind_ts(&bind.to_string(), span));
+                    body_ts.extend(spanned_as("=", span));
+                    body_ts.extend(call_ts);
+                    body_ts.extend(once(semi()));
+                    body_ts.extend(std::mem::take(&mut m.body));
+                } else if attr.out_ident.is_none() || m.out.is_empty() {
+                    enum_doc.push_str(&format!("\n    {call_run}"));
+                    body_ts.extend(call_ts);
+                    if m.out.is_empty() {
+                        enum_doc.push(';');
+                        body_ts.extend(once(semi()));
+                    }
+                } else if let Some(out_ident) = &attr.out_ident {
+                    enum_doc.push_str(&format!("\n    match {call_run} {{"));
+                    body_ts.extend(spanned_as("match", span));
+                    body_ts.extend(call_ts);
+                    // the arms are spanned by the return type of the signature
+                    let span = m.out_span.map_or(span, |out_span| span.located_at(out_span));
+                    let out_enum = out_ident.to_string() + "::";
+                    let varname = (m.bind.as_ref().or(attr.bind.as_ref()))
+                        .map_or(format!("_{}", out_ident).to_lowercase(), |b| b.to_string());
+                    let lside = if attr.strict_types {
+                        format!("{out_enum}{ident}(x)")
+                    } else {
+                        // the variants of other methods can be removed by their `#[cfg(..)]`
+                        (outs.iter())
+                            .filter(|(n, o, _, _, cfg, _)| {
+                                o == &out && (cfg.is_empty() || *n == ident.to_string())
+                            })
+                            .map(|(n, ..)| out_enum.clone() + n + "(x)")
+                            .reduce(|s, n| s + " | " + &n)
+                            .unwrap()
+                    };
+                    let x = if is_try { "Ok(x)" } else { "x" };
+                    enum_doc.push_str(&format!("\n        {lside} => {x},\n        {varname} => "));
+                    let mut match_ts = spanned_as(&format!("{lside} => {x},"), span);
+                    match_ts.extend(m.bind_ts(&varname, span));
+                    match_ts.extend(spanned_as("=>", span));
+                    if is_try {
+                        let err_s = format!(
+                            "Err({mismatch} {{ method: \"{ident}\", found: {varname}.stype() }})"
+                        );
+                        enum_doc.push_str(&err_s);
+                        match_ts.extend(spanned_as(&err_s, span));
+                    } else if let (Some(f), true) = (&attr.fallback, m.body.is_empty()) {
+                        let fallback_s = format!("Self::{f}(\"{ident}\", {varname})");
+                        enum_doc.push_str(&fallback_s);
+                        match_ts.extend(spanned_as(&fallback_s, span));
+                    } else if m.body.is_empty() {
+                        let panic_s = format!(
+                            "panic!(\"Type mismatch in the {ident}() method:
                     expected- {},
                     found- {out_enum}{{}}\", {varname}.stype())",
-                        lside
-                            .replace("(x)", &format!("({out})"))
-                            .replace(" | ", "\n                            | ")
-                    );
-                    enum_doc.push_str(&panic_s);
-                    match_ts.extend(TokenStream::from_str(&panic_s).unwrap());
-                } else {
-                    enum_doc.push_str(
-                        &ts_to_doc(&m.body)
-                            .replace(" {", " {\n            ")
-                            .replace(", _ =>", ",\n            _ =>"),
-                    );
-                    match_ts.extend(m.body);
+                            lside
+                                .replace("(x)", &format!("({out})"))
+                                .replace(" | ", "\n                            | ")
+                        );
+                        enum_doc.push_str(&panic_s);
+                        match_ts.extend(spanned_as(&panic_s, span));
+                    } else if m.bind.is_some() || attr.bind.is_some() {
+                        enum_doc.push_str(&format!("{{ {} }}", ts_to_doc(&m.body)));
+                        match_ts.extend(once(Group(Gr::new(Brace, std::mem::take(&mut m.body)))));
+                    } else {
+                        enum_doc.push_str(
+                            &ts_to_doc(&m.body)
+                                .replace(" {", " {\n            ")
+                                .replace(", _ =>", ",\n            _ =>"),
+                        );
+                        match_ts.extend(std::mem::take(&mut m.body));
+                    }
+                    enum_doc.push_str("\n    }");
+                    body_ts.extend(once(Group(Gr::new(Brace, match_ts))));
                 }
-                enum_doc.push_str("\n    }");
-                body_ts.extend(once(Group(Gr::new(Brace, match_ts))));
+                enum_doc.push_str("\n}");
+                methods_ts.extend(once(Group(Gr::new(Brace, body_ts))));
             }
-            enum_doc.push_str("\n}");
-            methods_ts.extend(once(Group(Gr::new(Brace, body_ts))));
         }
+
+        let (gen_wh, gen_wh_ts) = gens.wh(&[]);
+        res_ts.extend(TokenStream::from_str(&format!(
+            "{}{}{}{gen_wh}{}\"] enum ",
+            enum_head(attr.drv_dbg, &derives, &attr.attrs),
+            attr.enum_name,
+            gens.decl,
+            (enum_doc + &clone_s + "\n```").escape_debug()
+        )));
+        res_ts.extend(once(Ident(attr.enum_ident.clone().unwrap())));
+        res_ts.extend(gens.decl_ts.iter().chain(&gen_wh_ts).cloned());
+        res_ts.extend(once(Group(Gr::new(Brace, enum_ts))));
+        res_ts.extend(clone_ts);
+        res_ts.extend(kind_ts);
+        res_ts.extend(owned_ts);
+    }
+    let mut methods_ts = TokenStream::new();
+    for (m, body) in methods.into_iter().zip(bodies) {
+        methods_ts.extend(m.prev_ts);
+        methods_ts.extend(body);
     }
     methods_ts.extend(block_it);
     item_ts.extend(once(Group(Gr::new(Brace, methods_ts))));
 
-    let mut res_ts = TokenStream::from_str(&format!(
-        "{}{}{lftm}{}\"] enum ",
-        if attr.drv_dbg { head } else { &head_w_o_dbg },
-        attr.enum_name,
-        (enum_doc + "\n```").escape_debug().to_string()
-    ))
-    .unwrap();
-    res_ts.extend(once(Ident(attr.enum_ident.unwrap())));
-    res_ts.extend(TokenStream::from_str(lftm).unwrap());
-    res_ts.extend(once(Group(Gr::new(Brace, enum_ts))));
-
     res_ts.extend(item_ts);
 
     if let Some(out_ident) = &attr.out_ident {
-        enum_doc = " {\n    Unit,".to_string();
-        enum_ts = TokenStream::from_str("Unit, ").unwrap();
+        let o_span = out_ident.span();
+        let mut enum_doc = " {\n    Unit,".to_string();
+        let mut enum_ts = spanned("Unit, ", o_span);
         let indent = "\n            ";
         let mut stype = format!(
             "    fn stype(&self) -> &'static str {{
         match self {{{indent}{out_ident}::Unit => \"Unit\","
         );
-        let mut lftm = "";
-        for (name, mut out, span) in outs {
+        let mut stype_ts = spanned(&format!("{out_ident}::Unit => \"Unit\","), o_span);
+        let gens = head.generics(outs.iter().map(|t| &t.3), &attr.elided);
+        let (gen_use, (gen_wh, gen_wh_ts)) = (&gens.usage, gens.wh(&[]));
+        let (convs, convs_ts) = out_conversions(
+            out_ident,
+            outs.iter().filter(|t| t.4.is_empty()).map(|t| (&t.0, &t.3, &t.5[..])),
+            &gens,
+            attr.strict_types,
+        );
+        let mut out_derives = attr.out_derives.clone();
+        out_derives.extend(attr.serde_derives());
+        let unit = ("Unit".to_string(), String::new(), String::new(), String::new());
+        let clones: Vec<_> = once(unit)
+            .chain(outs.iter().map(|t| {
+                (
+                    t.0.clone(),
+                    "(ref _0)".to_string(),
+                    "(::core::clone::Clone::clone(_0))".to_string(),
+                    t.4.clone(),
+                )
+            }))
+            .collect();
+        let typs: Vec<(&String, &[TokenTree])> = outs.iter().map(|t| (&t.3, &t.5[..])).collect();
+        let (clone_s, clone_ts) =
+            clone_impls(&mut out_derives, &out_ident.to_string(), &gens, &clones, &typs);
+        let mut accessors = String::new();
+        let mut accessors_ts = TokenStream::new();
+        for (name, out_doc, span, out, cfg, out_ts) in outs {
+            enum_ts.extend(TokenStream::from_str(&cfg).unwrap());
             enum_ts.extend(once(Ident(Idn::new(&name, span))));
-            stype.push_str(&format!("{indent}{out_ident}::{name}(..) => \"{name}({out})\","));
-            if out.contains('&') {
-                lftm = "<'a>";
-                out = out.replace('&', "&'a ");
-            }
-            enum_ts.extend(TokenStream::from_str(&format!("({out}), ")));
-            enum_doc.push_str(&format!("\n    {name}({out}), "));
+            enum_ts.extend([
+                Group(Gr::new(Delimiter::Parenthesis, TokenStream::from_iter(out_ts.clone()))),
+                Punct(Pn::new(',', Spacing::Alone)),
+            ]);
+            enum_doc.push_str(&format!("\n    {cfg}{name}({out}), "));
+            let arm = format!("{cfg}{out_ident}::{name}(..) => \"{name}({out_doc})\",");
+            stype_ts.extend(spanned(&arm, span));
+            stype.push_str(&format!("{indent}{arm}"));
+            let (accessor, accessor_ts) = filled(
+                &format!(
+                    "\n\n    {cfg}pub fn into_{name}(self) -> Option<$0> {{
+        match self {{{indent}{out_ident}::{name}(x) => Some(x),{indent}_ => None,\n        }}\n    }}"
+                ),
+                span,
+                &[(&out, &out_ts)],
+            );
+            accessors_ts.extend(accessor_ts);
+            accessors.push_str(&accessor);
+        }
+        let mut match_ts = spanned("match self", o_span);
+        match_ts.extend(once(Group(Gr::new(Brace, stype_ts))));
+        let mut impl_ts = spanned("fn stype(&self) -> &'static str", o_span);
+        impl_ts.extend(once(Group(Gr::new(Brace, match_ts))));
+        impl_ts.extend(accessors_ts);
+        let gen_args = [(&gens.decl[..], &gens.decl_ts[..]), (&gen_wh[..], &gen_wh_ts[..])];
+        let (impl_s, mut out_ts) =
+            filled(&format!("impl$0 {out_ident}{gen_use}$1"), o_span, &gen_args);
+        out_ts.extend(once(Group(Gr::new(Brace, impl_ts))));
+        out_ts.extend(clone_ts);
+        out_ts.extend(convs_ts);
+
+        stype = format!("{impl_s} {{\n{stype}\n        }}\n    }}{accessors}\n}}");
+        let mut rest = clone_s + &convs;
+        if attr.try_out.is_some() {
+            let mismatch_s = format!(
+                "\n\n#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct {mismatch} {{
+    pub method: &'static str,
+    pub found: &'static str,
+}}
+
+impl ::core::fmt::Display for {mismatch} {{
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {{
+        ::core::write!(f, \"Type mismatch in the {{}}() method: found- {out_ident}::{{}}\", self.method, self.found)
+    }}
+}}
+
+impl ::std::error::Error for {mismatch} {{}}"
+            );
+            out_ts.extend(spanned(&mismatch_s, o_span));
+            rest.push_str(&mismatch_s);
         }
-        stype = format!("impl{lftm} {out_ident}{lftm} {{\n{stype}\n        }}\n    }}\n}}");
-        enum_doc = (enum_doc + "\n}\n\n" + &stype + "\n```").escape_debug().to_string();
+        enum_doc = (enum_doc + "\n}\n\n" + &stype + &rest + "\n```").escape_debug().to_string();
 
         res_ts.extend(TokenStream::from_str(&format!(
-            "{}{out_ident}{lftm}{enum_doc}\"] enum ",
-            if attr.out_dbg { head } else { &head_w_o_dbg }
+            "{}{out_ident}{}{gen_wh}{enum_doc}\"] enum ",
+            enum_head(attr.out_dbg, &out_derives, &attr.out_attrs),
+            gens.decl
         )));
         res_ts.extend(once(Ident(out_ident.clone())));
-        res_ts.extend(TokenStream::from_str(lftm).unwrap());
+        res_ts.extend(gens.decl_ts.iter().chain(&gen_wh_ts).cloned());
         res_ts.extend(once(Group(Gr::new(Brace, enum_ts))));
-        res_ts.extend(TokenStream::from_str(&stype).unwrap());
+        res_ts.extend(out_ts);
     }
 
-    if std::env::var("M_ENUM_DBG").map_or(false, |v| &v != "0") {
+    if std::env::var("M_ENUM_DBG").is_ok_and(|v| &v != "0") {
         println!(
             "\nM_ENUM_DBG - output to compiler input for enum {}:\n{}\n",
             attr.enum_name, res_ts
@@ -490,6 +2319,784 @@ pub fn gen(attr_ts: TokenStream, item_ts: TokenStream) -> TokenStream {
     res_ts
 }
 
+const STUB: &str = "{ #![allow(unused_variables)] ::core::unimplemented!() }";
+
+/// the item of the attribute with the error `err`: the method signatures in its block get
+/// the bodies `STUB`, so that only `err` is reported
+fn with_stubs(item_ts: TokenStream, err: TokenStream) -> TokenStream {
+    let mut res_ts = TokenStream::new();
+    for tt in item_ts {
+        match tt {
+            Group(gr) if gr.delimiter() == Brace => {
+                let mut block = TokenStream::new();
+                let mut sig = false; // after `fn` up to its body
+                for tt in gr.stream() {
+                    match tt {
+                        Ident(id) if id.to_string() == "fn" => {
+                            sig = true;
+                            block.extend(once(Ident(id)))
+                        }
+                        Punct(p) if p.as_char() == ';' && sig => {
+                            sig = false;
+                            block.extend(spanned(STUB, p.span()))
+                        }
+                        Group(g) if g.delimiter() == Brace => {
+                            sig = false;
+                            block.extend(once(Group(g)))
+                        }
+                        tt => block.extend(once(tt)),
+                    }
+                }
+                let mut block = Gr::new(Brace, block);
+                block.set_span(gr.span());
+                res_ts.extend(once(Group(block)))
+            }
+            tt => res_ts.extend(once(tt)),
+        }
+    }
+    res_ts.extend(err);
+    res_ts
+}
+
+/// Collects the method signatures of an additional impl block into `enum` *EnumName* of
+/// [`#[gen]`](attr.gen.html) with the option `parts(`*label*`, ..)`:
+/// `#[methods_enum::gen_part(EnumName, label)]`.
+///
+/// The block is replaced by the macro *label* with its signatures, which can be used
+/// by its name or path (`crate::api::label`) in the module of the `#[gen]` block and in the rest of the crate.
+/// The block must contain only the method signatures (in *OutName* mode - also with default bodies),
+/// its header is not used. See [gen macro details](attr.gen.html#parts).
+///
+/// The macro *label* calls `::methods_enum::gen_parts!`: if the crate is renamed or re-exported,
+/// its path is set by the option `crate`: `#[gen_part(EnumName, label; crate = path::to::methods_enum)]`.
+#[proc_macro_attribute]
+pub fn gen_part(attr_ts: TokenStream, item_ts: TokenStream) -> TokenStream {
+    let tts: Vec<TokenTree> = attr_ts.into_iter().collect();
+    let (head, opt) = match tts.iter().position(|tt| is_punct(tt, ';')) {
+        Some(i) => (&tts[..i], Some(&tts[i + 1..])),
+        None => (&tts[..], None),
+    };
+    let (enum_i, label) = match head {
+        [Ident(enum_i), Punct(p), Ident(label)] if p.as_char() == ',' => {
+            (enum_i.clone(), label.clone())
+        }
+        tts => {
+            let span = tts.first().map_or(Span::call_site(), |tt| tt.span());
+            return compile_error(
+                "#[gen_part]: Syntax error in attribute, expected: \
+`#[methods_enum::gen_part(EnumName, label)]`",
+                span,
+            );
+        }
+    };
+    // the path of this crate for the call of `gen_parts!`: option `crate = path`
+    let krate = match opt {
+        None => TokenStream::from_str("::methods_enum").unwrap(),
+        Some([Ident(k), Punct(p), path @ ..])
+            if k.to_string() == "crate"
+                && p.as_char() == '='
+                && matches!(path.last(), Some(Ident(_)))
+                && path.iter().all(|tt| matches!(tt, Ident(_)) || is_punct(tt, ':')) =>
+        {
+            TokenStream::from_iter(path.iter().cloned())
+        }
+        Some(tts) => {
+            let span = tts.first().map_or(label.span(), |tt| tt.span());
+            return compile_error(
+                "#[gen_part]: Syntax error in attribute options after `;`, \
+expected: `crate = path::to::methods_enum`",
+                span,
+            );
+        }
+    };
+    let Some(sigs) = item_ts.into_iter().find_map(|tt| match tt {
+        Group(gr) if gr.delimiter() == Brace => Some(gr),
+        _ => None,
+    }) else {
+        return compile_error(
+            "#[gen_part]: attribute must be set on an `impl` block",
+            label.span(),
+        );
+    };
+    let mut call_ts = krate;
+    call_ts.extend(TokenStream::from_str("::gen_parts!").unwrap());
+    let mut args_ts = TokenStream::from_iter([Ident(enum_i), Group(sigs)]);
+    args_ts.extend(TokenStream::from_str("$($rest)*").unwrap());
+    call_ts.extend(once(Group(Gr::new(Brace, args_ts))));
+    let mut rule_ts = TokenStream::from_str("($($rest:tt)*) =>").unwrap();
+    rule_ts.extend([Group(Gr::new(Brace, call_ts)), Punct(Pn::new(';', Spacing::Alone))]);
+
+    let mut res_ts = TokenStream::from_str("#[allow(unused_macros)] macro_rules!").unwrap();
+    res_ts.extend([Ident(label.clone()), Group(Gr::new(Brace, rule_ts))]);
+    res_ts.extend(TokenStream::from_str("#[allow(unused_imports)] pub(crate) use").unwrap());
+    res_ts.extend([Ident(label), Punct(Pn::new(';', Spacing::Alone))]);
+    res_ts
+}
+
+/// Internal: `{..} EnumName {sigs} .. [parts] (attr) {item}` from the macros of `#[gen_part]`
+#[doc(hidden)]
+#[proc_macro]
+pub fn gen_parts(input_ts: TokenStream) -> TokenStream {
+    let mut tts: Vec<TokenTree> = input_ts.into_iter().collect();
+    let (Some(Group(item)), Some(Group(attr)), Some(Group(parts))) =
+        (tts.pop(), tts.pop(), tts.pop())
+    else {
+        return compile_error("#[gen_part]: invalid call of `gen_parts!`", Span::call_site());
+    };
+    if !parts.stream().is_empty() {
+        // to the next part
+        let mut parts = split_top(parts.stream(), ',');
+        let mut res_ts = TokenStream::from_iter(parts.remove(0));
+        res_ts.extend(once(Punct(Pn::new('!', Spacing::Alone))));
+        tts.extend([
+            Group(Gr::new(
+                Delimiter::Bracket,
+                TokenStream::from_iter(parts.join(&Punct(Pn::new(',', Spacing::Alone)))),
+            )),
+            Group(attr),
+            Group(item),
+        ]);
+        res_ts.extend(once(Group(Gr::new(Brace, TokenStream::from_iter(tts)))));
+        return res_ts;
+    }
+    let enum_name = attr.stream().into_iter().next().map(|tt| tt.to_string()).unwrap_or_default();
+    let mut sigs = TokenStream::new();
+    let mut err = TokenStream::new();
+    // the parts are collected in reverse order
+    for part in tts.chunks(2).rev() {
+        match part {
+            [Ident(enum_i), Group(gr)] if enum_i.to_string() == enum_name => {
+                sigs.extend(gr.stream())
+            }
+            [Ident(enum_i), _] => err.extend(compile_error(
+                &format!(
+                    "#[gen_part]: the part for `enum {enum_i}` is included in \
+`#[gen({enum_name}, ..)]`"
+                ),
+                enum_i.span(),
+            )),
+            _ => (),
+        }
+    }
+    let mut res_ts = gen_impl(attr.stream(), item.stream(), Some(sigs));
+    res_ts.extend(err);
+    res_ts
+}
+
+/// `{EnumName}Owned` with owned types of the borrowed arguments,
+/// `From<EnumName> for {EnumName}Owned` and `apply(self, target: &mut SelfType)`
+fn owned_enum(attr: &Attr, head: &ImplHead, methods: &[&Meth], m_gens: &Gens) -> TokenStream {
+    let (name, owned) = (&attr.enum_name, format!("{}Owned", attr.enum_name));
+    struct Field {
+        name: String,
+        ty: (String, Vec<TokenTree>),
+        /// the borrowed type for a reference
+        inner: Option<(String, Vec<TokenTree>)>,
+        /// the predicate for `to_owned()` of the generic `inner`
+        pred: Option<(String, Vec<TokenTree>)>,
+        is_mut: bool,
+    }
+    //            (method, fields, has a return type, `cfg()`, `arm_attrs()`)
+    let mut vars: Vec<(&Idn, Vec<Field>, bool, String, String)> = Vec::new();
+    for m in methods.iter().filter(|m| m.err.is_none()) {
+        let Some(ident) = &m.ident else { continue };
+        if m.is_async {
+            return compile_error(
+                &format!(
+                    "#[gen]: option `owned` does not support `async` method `{ident}()`: \
+`apply()` cannot await it"
+                ),
+                ident.span(),
+            );
+        }
+        if !m.self_ref() {
+            return compile_error(
+                &format!(
+                    "#[gen]: option `owned` requires `&self` or `&mut self` in method `{ident}()` \
+to replay it with `apply(self, target: &mut ..)`"
+                ),
+                ident.span(),
+            );
+        }
+        let mut fields = Vec::new();
+        for (((n, ty), ty_ts), (_, arg_ty, _)) in
+            m.names.iter().zip(&m.typs).zip(&m.typ_ts).zip(&m.arg_tys)
+        {
+            // the lifetimes left after the conversion of a top-level reference
+            let r = ty
+                .strip_prefix('&')
+                .map(|r| r.strip_prefix('\'').map_or(r, |r| r.split_once(' ').map_or(r, |t| t.1)));
+            let rest = r.map_or(&ty[..], |r| r.strip_prefix("mut ").unwrap_or(r)).to_string();
+            if !lftms(once(&rest), "").is_empty() {
+                return compile_error(
+                    &format!(
+                        "#[gen]: option `owned` cannot own the argument `{n}: {}` of method \
+`{ident}()`: only a top-level reference `&T` or `&mut T` is converted to its owned type, \
+and the other types must not borrow",
+                        ts_to_doc(&TokenStream::from_iter(arg_ty.iter().cloned()))
+                    ),
+                    arg_ty[0].span(),
+                );
+            }
+            if !is_punct(&ty_ts[0], '&') {
+                let (name, ty) = (n.clone(), (ty.clone(), ty_ts.clone()));
+                fields.push(Field { name, ty, inner: None, pred: None, is_mut: false });
+                continue;
+            }
+            // `&`, `'a`, `mut`
+            let mut i = if is_punct(&ty_ts[1], '\'') { 3 } else { 1 };
+            let is_mut = is_ident(&ty_ts[i], "mut");
+            i += is_mut as usize;
+            let inner = (ty_str_raw(&ty_ts[i..]), ty_ts[i..].to_vec());
+            if inner.0.starts_with("(dyn ") {
+                return compile_error(
+                    &format!(
+                        "#[gen]: option `owned`: the argument `{n}` of method `{ident}()` \
+of the erased generic type cannot be made owned"
+                    ),
+                    ident.span(),
+                );
+            }
+            let span = ty_ts[0].span();
+            // (owned type, predicate for `to_owned()`)
+            let (ty, pred) = match &inner.1[..] {
+                _ if inner.0 == "str" => (("String".to_string(), spanned("String", span)), None),
+                [Group(gr)] if gr.delimiter() == Delimiter::Bracket && !inner.0.contains(';') => {
+                    let el: Vec<TokenTree> = gr.stream().into_iter().collect();
+                    let el = (ty_str_raw(&el), el);
+                    (
+                        filled("Vec<$0>", span, &[(&el.0, &el.1)]),
+                        Some(pred((&el.0, &el.1), "::core::clone::Clone")),
+                    )
+                }
+                _ => (
+                    filled("<$0 as ::std::borrow::ToOwned>::Owned", span, &[(&inner.0, &inner.1)]),
+                    Some(pred((&inner.0, &inner.1), "::std::borrow::ToOwned")),
+                ),
+            };
+            let ty = (ty.0, ty.1.into_iter().collect());
+            fields.push(Field { name: n.clone(), ty, inner: Some(inner), pred, is_mut });
+        }
+        vars.push((ident, fields, !m.out.is_empty(), m.cfg(), m.arm_attrs()));
+    }
+    let all: Vec<&Field> = vars.iter().flat_map(|v| &v.1).collect();
+    let mut o_gens = head.generics(all.iter().map(|f| &f.ty.0), &attr.elided);
+    let tparams = type_params(&m_gens.usage);
+    let generic = |ty: &str| tparams.iter().any(|p| has_word(ty, p));
+    let mut to_owned_preds: Vec<(String, Vec<TokenTree>)> = Vec::new();
+    let mut borrow_preds: Vec<(String, Vec<TokenTree>)> = Vec::new();
+    for f in &all {
+        if let Some(inner) = f.inner.as_ref().filter(|i| generic(&i.0)) {
+            if let Some(pred) =
+                f.pred.as_ref().filter(|p| !to_owned_preds.iter().any(|t| t.0 == p.0))
+            {
+                to_owned_preds.push(pred.clone());
+            }
+            let pred = format!("{}: ::core::borrow::BorrowMut<{}>", f.ty.0, inner.0);
+            if f.is_mut && !borrow_preds.iter().any(|p| p.0 == pred) {
+                let args: [&[TokenTree]; 2] = [&f.ty.1, &inner.1];
+                let pred_ts =
+                    spanned_with("$0: ::core::borrow::BorrowMut<$1>", Span::call_site(), &args);
+                borrow_preds.push((pred, pred_ts.into_iter().collect()));
+            }
+        }
+    }
+    let named = attr.named;
+    let shape = |fs: Vec<String>, names: &mut dyn Iterator<Item = &String>| {
+        if named {
+            let fs: Vec<String> = names.zip(fs).map(|(n, f)| format!("{n}: {f}")).collect();
+            format!(" {{ {} }}", fs.join(", "))
+        } else {
+            format!("({})", fs.join(", "))
+        }
+    };
+    let mut enum_doc = " {".to_string();
+    let mut enum_ts = TokenStream::new();
+    let mut from_arms = String::new();
+    let mut apply_arms = String::new();
+    let indent = "\n            ";
+    for (ident, fields, has_out, cfg, arm) in &vars {
+        let names = || fields.iter().map(|f| &f.name);
+        let holes = (0..fields.len()).map(|i| format!("${i}")).collect();
+        let typs: Vec<(&str, &[TokenTree])> =
+            fields.iter().map(|f| (&f.ty.0[..], &f.ty.1[..])).collect();
+        let (var, var_ts) =
+            filled(&format!("{cfg}{ident}{}, ", shape(holes, &mut names())), ident.span(), &typs);
+        enum_doc.push_str(&format!("\n    {var}"));
+        enum_ts.extend(var_ts);
+        let binds = (0..fields.len()).map(|i| format!("_{i}")).collect();
+        let to_owned = (fields.iter().enumerate())
+            .map(|(i, f)| match f.inner {
+                Some(_) => format!("::std::borrow::ToOwned::to_owned(&*_{i})"),
+                None => format!("_{i}"),
+            })
+            .collect();
+        from_arms.push_str(&format!(
+            "{indent}{arm}{name}::{ident}{} => {owned}::{ident}{},",
+            shape(binds, &mut names()),
+            shape(to_owned, &mut names())
+        ));
+        let binds = (fields.iter().enumerate())
+            .map(|(i, f)| format!("{}_{i}", if f.is_mut { "mut " } else { "" }))
+            .collect();
+        let args: Vec<String> = (fields.iter().enumerate())
+            .map(|(i, f)| match (&f.inner, f.is_mut) {
+                (None, _) => format!("_{i}"),
+                (Some(_), false) => format!("::core::borrow::Borrow::borrow(&_{i})"),
+                (Some(_), true) => format!("::core::borrow::BorrowMut::borrow_mut(&mut _{i})"),
+            })
+            .collect();
+        apply_arms.push_str(&format!(
+            "{indent}{arm}{owned}::{ident}{} => {{\n                {}target.{ident}({});\n            }}",
+            shape(binds, &mut names()),
+            if *has_out { "let _ = " } else { "" },
+            args.join(", ")
+        ));
+    }
+    let (from_wh, from_wh_ts) = m_gens.wh(&to_owned_preds);
+    o_gens.preds.extend(to_owned_preds);
+    let (o_wh, o_wh_ts) = o_gens.wh(&[]);
+    let rest = head.rest(&o_gens.usage);
+    let (apply_wh, apply_wh_ts) = rest.wh(&borrow_preds);
+    let (m_use, o_use) = (&m_gens.usage, &o_gens.usage);
+    let (impls, impls_ts) = filled(
+        &format!(
+            "impl$0 From<{name}{m_use}> for {owned}{o_use}$1 {{
+    fn from(meth: {name}{m_use}) -> Self {{
+        match meth {{{from_arms}
+        }}
+    }}
+}}
+
+impl$2 {owned}{o_use}$3 {{
+    /// calls the method of this variant on `target`
+    pub fn apply$4(self, target: &mut {})$5 {{
+        match self {{{apply_arms}
+        }}
+    }}
+}}",
+            head.self_ty
+        ),
+        Span::call_site(),
+        &[
+            (&m_gens.decl, &m_gens.decl_ts),
+            (&from_wh, &from_wh_ts),
+            (&o_gens.decl, &o_gens.decl_ts),
+            (&o_wh, &o_wh_ts),
+            (&rest.decl, &rest.decl_ts),
+            (&apply_wh, &apply_wh_ts),
+        ],
+    );
+    let mut derives: Vec<String> = attr.derives.iter().filter(|d| *d != "Copy").cloned().collect();
+    derives.extend(attr.serde_derives());
+    let clones: Vec<_> = (vars.iter())
+        .map(|(ident, fields, _, cfg, _)| {
+            let names = || fields.iter().map(|f| &f.name);
+            let binds = (0..fields.len()).map(|i| format!("ref _{i}")).collect();
+            let clones =
+                (0..fields.len()).map(|i| format!("::core::clone::Clone::clone(_{i})")).collect();
+            let (binds, clones) = (shape(binds, &mut names()), shape(clones, &mut names()));
+            (ident.to_string(), binds, clones, cfg.clone())
+        })
+        .collect();
+    let typs: Vec<(&String, &[TokenTree])> = all.iter().map(|f| (&f.ty.0, &f.ty.1[..])).collect();
+    let (clone_s, clone_ts) = clone_impls(&mut derives, &owned, &o_gens, &clones, &typs);
+    let mut ts = TokenStream::from_str(&format!(
+        "{}{owned}{}{o_wh}{}\"] enum {owned}",
+        enum_head(attr.drv_dbg, &derives, &attr.attrs),
+        o_gens.decl,
+        (enum_doc + "\n}\n\n" + &impls + &clone_s + "\n```").escape_debug(),
+    ))
+    .unwrap();
+    ts.extend(o_gens.decl_ts.iter().chain(&o_wh_ts).cloned());
+    ts.extend(once(Group(Gr::new(Brace, enum_ts))));
+    ts.extend(impls_ts);
+    ts.extend(clone_ts);
+    ts
+}
+
+/// type parameters from the generic arguments of a generated `enum`: `<'a, T>` -> `T`
+fn type_params(gen_use: &str) -> Vec<&str> {
+    (gen_use.trim_matches(['<', '>']).split(", "))
+        .filter(|p| !p.is_empty() && !p.starts_with('\''))
+        .collect()
+}
+
+/// for an `enum` with type parameters, derived `Clone` and `Copy` are removed from `derives`
+/// and implemented with bounds on the field types instead of the type parameters,
+/// so that the fields `&'a T` do not require `T: Clone`.
+/// `variants`: (name, pattern binding the fields to `_0, _1, ..`, the same with `Clone::clone(_0)`..,
+/// attributes of the arm); `typs`: the field types (string, tokens). Returns: (doc, code)
+fn clone_impls(
+    derives: &mut Vec<String>,
+    enum_name: &str,
+    gens: &Gens,
+    variants: &[(String, String, String, String)],
+    typs: &[(&String, &[TokenTree])],
+) -> (String, TokenStream) {
+    let tparams = type_params(&gens.usage);
+    let (mut res, mut res_ts) = (String::new(), TokenStream::new());
+    if tparams.is_empty() {
+        return (res, res_ts);
+    }
+    let mut preds: Vec<&(&String, &[TokenTree])> = Vec::new();
+    for ty in typs.iter().filter(|ty| tparams.iter().any(|p| has_word(ty.0, p))) {
+        if !preds.iter().any(|p| p.0 == ty.0) {
+            preds.push(ty);
+        }
+    }
+    let wh = |bound: &str| {
+        let preds: Vec<_> = preds.iter().map(|ty| pred((ty.0, ty.1), bound)).collect();
+        gens.wh(&preds)
+    };
+    let gen_use = &gens.usage;
+    let mut push = |s: String, wh: (String, Vec<TokenTree>)| {
+        let args = [(&gens.decl[..], &gens.decl_ts[..]), (&wh.0[..], &wh.1[..])];
+        let (impl_s, impl_ts) = filled(&s, Span::call_site(), &args);
+        res.push_str(&format!("\n\n{impl_s}"));
+        res_ts.extend(impl_ts);
+    };
+    if let Some(i) = derives.iter().position(|d| d == "Clone") {
+        derives.remove(i);
+        let arms: String = (variants.iter())
+            .map(|(n, bind, clone, cfg)| {
+                format!("\n            {cfg}{enum_name}::{n}{bind} => {enum_name}::{n}{clone},")
+            })
+            .collect();
+        push(
+            format!(
+                "impl$0 ::core::clone::Clone for {enum_name}{gen_use}$1 {{
+    fn clone(&self) -> Self {{
+        match *self {{{arms}
+        }}
+    }}
+}}"
+            ),
+            wh("::core::clone::Clone"),
+        );
+    }
+    if let Some(i) = derives.iter().position(|d| d == "Copy") {
+        derives.remove(i);
+        push(
+            format!("impl$0 ::core::marker::Copy for {enum_name}{gen_use}$1 {{}}"),
+            wh("::core::marker::Copy"),
+        );
+    }
+    (res, res_ts)
+}
+
+/// attributes and the beginning of the doc-comment of a generated `enum`
+fn enum_head(dbg: bool, derives: &[String], attrs: &[String]) -> String {
+    let derives: Vec<&str> =
+        (dbg.then_some("Debug").into_iter()).chain(derives.iter().map(|d| &d[..])).collect();
+    let mut attrs: Vec<String> = attrs.iter().map(|a| format!("#[{a}]")).collect();
+    if !derives.is_empty() {
+        attrs.insert(0, format!("#[derive({})]", derives.join(", ")));
+    }
+    attrs.push("#[allow(non_camel_case_types)]".to_string());
+    format!(
+        "{}\n/// Formed by macro [`#[methods_enum::gen(...)]`](https://docs.rs/methods-enum):
+/// ```\n{}\n#[doc = \"enum ",
+        attrs.join("\n"),
+        attrs.iter().map(|a| format!("/// {a}")).collect::<Vec<_>>().join("\n")
+    )
+}
+
+/// `From<T> for OutName` and `TryFrom<OutName> for T` for the return types `T`:
+/// with `!` - only for the types of one variant, otherwise - for all types by the first variant
+/// (`From`) or by all variants (`TryFrom`) of the type; `outs` - (variant name, type, its tokens)
+/// of the variants without `#[cfg(..)]`. Returns: (doc, code)
+fn out_conversions<'s>(
+    out_ident: &Idn,
+    outs: impl Iterator<Item = (&'s String, &'s String, &'s [TokenTree])>,
+    gens: &Gens,
+    strict_types: bool,
+) -> (String, TokenStream) {
+    let (gen_use, (gen_wh, gen_wh_ts)) = (&gens.usage, gens.wh(&[]));
+    let tparams = type_params(gen_use);
+    let mut typs: Vec<(&String, &[TokenTree], Vec<&String>)> = Vec::new();
+    for (name, out, out_ts) in outs {
+        match typs.iter_mut().find(|t| t.0 == out) {
+            Some((.., names)) => names.push(name),
+            None => typs.push((out, out_ts, vec![name])),
+        }
+    }
+    let tts: Vec<Vec<TokenTree>> =
+        typs.iter().map(|t| TokenStream::from_str(t.0).unwrap().into_iter().collect()).collect();
+    let (mut res, mut res_ts) = (String::new(), TokenStream::new());
+    for (i, (ty, ty_ts, names)) in typs.into_iter().enumerate() {
+        // the types that can be the same for some parameters would give overlapping impls
+        // (also those differing only in lifetimes: `&'a str` and `&'x str`)
+        let overlaps = (tts.iter().enumerate())
+            .any(|(j, t)| j != i && unify(&tts[i], t, &tparams, &mut Vec::new()));
+        // a type parameter not covered by a local type (orphan rules)
+        if overlaps || (strict_types && names.len() > 1) || uncovered(&tts[i], &tparams) {
+            continue;
+        }
+        let lside = names
+            .iter()
+            .map(|n| format!("{out_ident}::{n}(x)"))
+            .collect::<Vec<_>>()
+            .join("\n            | ");
+        let (conv, conv_ts) = filled(
+            &format!(
+                "impl$0 From<$2> for {out_ident}{gen_use}$1 {{
+    fn from(x: $2) -> Self {{
+        {out_ident}::{}(x)
+    }}
+}}
+
+impl$0 ::core::convert::TryFrom<{out_ident}{gen_use}> for $2$1 {{
+    type Error = {out_ident}{gen_use};
+    fn try_from(out: {out_ident}{gen_use}) -> Result<Self, Self::Error> {{
+        match out {{
+            {lside} => Ok(x),
+            out => Err(out),
+        }}
+    }}
+}}",
+                names[0]
+            ),
+            out_ident.span(),
+            &[(&gens.decl, &gens.decl_ts), (&gen_wh, &gen_wh_ts), (ty, ty_ts)],
+        );
+        res.push_str(&format!("\n\n{conv}"));
+        res_ts.extend(conv_ts);
+    }
+    (res, res_ts)
+}
+
+/// the number of tokens of the type at the beginning of `tts`: up to `,`, `;`, `=` or `>` outside of `<..>`
+fn type_len(tts: &[TokenTree]) -> usize {
+    let (mut lg, mut dash) = (0, false);
+    for (i, tt) in tts.iter().enumerate() {
+        match tt {
+            Punct(p) if ",;=".contains(p.as_char()) && lg == 0 => return i,
+            Punct(p) if p.as_char() == '<' => lg += 1,
+            Punct(p) if p.as_char() == '>' && !dash => {
+                if lg == 0 {
+                    return i;
+                }
+                lg -= 1
+            }
+            _ => (),
+        }
+        dash = is_punct(tt, '-');
+    }
+    tts.len()
+}
+
+/// `true` if the types `a` and `b` can be the same for some values of the parameters `vars`,
+/// which are bound in `subst`; lifetimes can be any, projections `T::Item` - any type
+fn unify(
+    a: &[TokenTree],
+    b: &[TokenTree],
+    vars: &[&str],
+    subst: &mut Vec<(String, Vec<TokenTree>)>,
+) -> bool {
+    let var = |tt: &TokenTree| matches!(tt, Ident(id) if vars.contains(&&id.to_string()[..]));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let (ta, tb) = (&a[i], &b[j]);
+        if var(ta) || var(tb) {
+            let (la, lb) = (type_len(&a[i..]), type_len(&b[j..]));
+            let (ty_a, ty_b) = (&a[i..i + la], &b[j..j + lb]);
+            let ok = match (ty_a, ty_b) {
+                ([v @ Ident(_)], ty) | (ty, [v @ Ident(_)]) if var(v) => bind(v, ty, vars, subst),
+                _ => true, // a projection
+            };
+            if !ok {
+                return false;
+            }
+            (i, j) = (i + la, j + lb);
+            continue;
+        }
+        match (ta, tb) {
+            (Punct(p), Punct(q)) if p.as_char() == '\'' && q.as_char() == '\'' => {
+                (i, j) = (i + 2, j + 2);
+                continue;
+            }
+            (Group(ga), Group(gb)) if ga.delimiter() == gb.delimiter() => {
+                let ga: Vec<TokenTree> = ga.stream().into_iter().collect();
+                let gb: Vec<TokenTree> = gb.stream().into_iter().collect();
+                if !unify(&ga, &gb, vars, subst) {
+                    return false;
+                }
+            }
+            (Ident(_), Ident(_)) | (Punct(_), Punct(_)) | (Literal(_), Literal(_))
+                if ta.to_string() == tb.to_string() => {}
+            _ => return false,
+        }
+        (i, j) = (i + 1, j + 1);
+    }
+    i == a.len() && j == b.len()
+}
+
+/// binds the parameter `v` to the type `ty` in `subst` (for `unify()`), `false` if it's impossible
+fn bind(
+    v: &TokenTree,
+    ty: &[TokenTree],
+    vars: &[&str],
+    subst: &mut Vec<(String, Vec<TokenTree>)>,
+) -> bool {
+    let name = v.to_string();
+    if let Some((_, bound)) = subst.iter().find(|(n, _)| *n == name) {
+        return unify(&bound.clone(), ty, vars, subst);
+    }
+    let ty = substitute(ty, subst);
+    match &ty[..] {
+        [Ident(id)] if id.to_string() == name => true,
+        _ if has_word(&ty_str_raw(&ty), &name) => false, // `T` and `Vec<T>`
+        _ => {
+            for (_, bound) in subst.iter_mut() {
+                *bound = substitute(bound, &[(name.clone(), ty.clone())]);
+            }
+            subst.push((name, ty));
+            true
+        }
+    }
+}
+
+/// `ty` with the bound parameters replaced by their types
+fn substitute(ty: &[TokenTree], subst: &[(String, Vec<TokenTree>)]) -> Vec<TokenTree> {
+    let mut res = Vec::new();
+    for tt in ty {
+        match tt {
+            Ident(id) => match subst.iter().find(|(n, _)| *n == id.to_string()) {
+                Some((_, bound)) => res.extend(bound.iter().cloned()),
+                None => res.push(tt.clone()),
+            },
+            Group(gr) => {
+                let inner: Vec<TokenTree> = gr.stream().into_iter().collect();
+                let inner = TokenStream::from_iter(substitute(&inner, subst));
+                res.push(Group(Gr::new(gr.delimiter(), inner)))
+            }
+            _ => res.push(tt.clone()),
+        }
+    }
+    res
+}
+
+/// `true` if the type is a type parameter of `vars` (or its projection `T::Item`) alone
+/// or under the fundamental types `&`, `&mut`, `Box<..>` and `Pin<..>`
+fn uncovered(ty: &[TokenTree], vars: &[&str]) -> bool {
+    match ty {
+        [Punct(p), Punct(q), Ident(_), rest @ ..] if p.as_char() == '&' && q.as_char() == '\'' => {
+            uncovered(rest, vars)
+        }
+        [Punct(p), rest @ ..] if p.as_char() == '&' => uncovered(rest, vars),
+        [Ident(m), rest @ ..] if m.to_string() == "mut" => uncovered(rest, vars),
+        [Ident(id), ..] if vars.contains(&&id.to_string()[..]) => true,
+        // `<T as Trait>::Item`
+        [Punct(p), ..] if p.as_char() == '<' => vars.iter().any(|v| has_word(&ty_str_raw(ty), v)),
+        _ => match ty.iter().position(|tt| is_punct(tt, '<')) {
+            Some(i) if i > 0 && (is_ident(&ty[i - 1], "Box") || is_ident(&ty[i - 1], "Pin")) => {
+                uncovered(&ty[i + 1..i + 1 + type_len(&ty[i + 1..])], vars)
+            }
+            _ => false,
+        },
+    }
+}
+
+/// `{EnumName}Kind` with `ALL` and `name()`, `kind()` and `name()` for *EnumName*
+/// and `Display` for *EnumName* if it derives `Debug`
+fn kind_enum(
+    attr: &Attr,
+    kinds: &[(String, String, String, usize, String)],
+    gens: &Gens,
+    methods: &[&Meth],
+) -> TokenStream {
+    let gen_use = &gens.usage;
+    let (name, kind) = (&attr.enum_name, format!("{}Kind", attr.enum_name));
+    // the variants with their attributes
+    let names: Vec<String> = kinds.iter().map(|k| k.4.clone() + &k.0).collect();
+    let (cfgs, plain): (Vec<_>, Vec<_>) = kinds.iter().partition(|k| k.4.starts_with("#[cfg"));
+    // the number of variants left by `#[cfg(..)]`
+    let len = match cfgs.is_empty() {
+        true => plain.len().to_string(),
+        false => format!(
+            "{{ let mut n = {}; {}n }}",
+            plain.len(),
+            cfgs.iter().map(|k| k.4.clone() + "{ n += 1; } ").collect::<String>()
+        ),
+    };
+    let indent = "\n            ";
+    let mut kind_impl = format!(
+        "impl {kind} {{
+    pub const ALL: [{kind}; {len}] = [{}];
+
+    pub fn name(self) -> &'static str {{
+        match self {{",
+        (kinds.iter()).map(|k| format!("{}{kind}::{}", k.4, k.0)).collect::<Vec<_>>().join(", ")
+    );
+    let mut meth_impl = format!(
+        "impl$0 {name}{gen_use}$1 {{
+    pub fn kind(&self) -> {kind} {{
+        match *self {{"
+    );
+    for (n, pat, _, _, arm) in kinds {
+        kind_impl.push_str(&format!("{indent}{arm}{kind}::{n} => \"{n}\","));
+        meth_impl.push_str(&format!("{indent}{arm}{name}::{n}{pat} => {kind}::{n},"));
+    }
+    kind_impl.push_str("\n        }\n    }\n}");
+    meth_impl.push_str(
+        "\n        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.kind().name()
+    }
+}",
+    );
+    let mut impls = vec![(kind_impl, (String::new(), Vec::new())), (meth_impl, gens.wh(&[]))];
+    if attr.drv_dbg {
+        // like `#[derive(Debug)]`: type parameters of the fields are bounded by `Debug`
+        let typs: Vec<&String> =
+            methods.iter().filter(|m| m.err.is_none()).flat_map(|m| &m.typs).collect();
+        let preds: Vec<(String, Vec<TokenTree>)> = (type_params(gen_use).into_iter())
+            .filter(|p| typs.iter().any(|ty| has_word(ty, p)))
+            .map(|p| pred((p, &[Ident(Idn::new(p, Span::call_site()))]), "::core::fmt::Debug"))
+            .collect();
+        let mut fmt = format!(
+            "impl$0 ::core::fmt::Display for {name}{gen_use}$1 {{
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {{
+        match *self {{"
+        );
+        for (n, _, bind, len, arm) in kinds {
+            let args: Vec<String> = (0..*len).map(|i| format!("_{i}")).collect();
+            fmt.push_str(&format!(
+                "{indent}{arm}{name}::{n}{bind} => ::core::write!(f, \"{n}({})\"{}),",
+                vec!["{:?}"; *len].join(", "),
+                args.iter().map(|a| format!(", {a}")).collect::<String>()
+            ));
+        }
+        fmt.push_str("\n        }\n    }\n}");
+        impls.push((fmt, gens.wh(&preds)));
+    }
+    let (mut impls_s, mut impls_ts) = (Vec::new(), TokenStream::new());
+    for (s, wh) in impls {
+        let args = [(&gens.decl[..], &gens.decl_ts[..]), (&wh.0[..], &wh.1[..])];
+        let (impl_s, impl_ts) = filled(&s, Span::call_site(), &args);
+        impls_s.push(impl_s);
+        impls_ts.extend(impl_ts);
+    }
+    let impls = impls_s.join("\n\n");
+    let doc = format!(
+        " {{{}\n}}\n\n{impls}\n```",
+        names.iter().map(|n| format!("\n    {n}, ")).collect::<String>()
+    );
+    let derives = ["Clone", "Copy", "PartialEq", "Eq", "Hash"].map(String::from);
+    let mut ts = TokenStream::from_str(&format!(
+        "{}{kind}{}\"] enum {kind} {{ {} }}",
+        enum_head(true, &derives, &[]),
+        doc.escape_debug(),
+        names.iter().map(|n| format!("{n}, ")).collect::<String>()
+    ))
+    .unwrap();
+    ts.extend(impls_ts);
+    ts
+}
+
 // endregion: gen
 
 //     #####     #####     #####     #####     #####     #####     #####     #####
@@ -501,8 +3108,9 @@ use std::hash::{Hash, Hasher};
 use std::mem;
 
 struct Flags {
-    panic: bool,
     no_semnt: bool,
+    /// flag `trace` (cargo feature `tracing`): its span
+    trace: Option<Span>,
 }
 
 #[derive(Default)]
@@ -536,25 +3144,16 @@ impl Item {
         let mut item = Item::default();
         let mut lg = 0;
         let mut state = Args;
-        let mut flags = Flags { no_semnt: true, panic: true };
-        if cfg!(debug_assertions) {
-            flags.no_semnt = false;
-            flags.panic = false;
-        }
+        let mut flags = Flags { no_semnt: !cfg!(debug_assertions), trace: None };
         for tt in ts {
             state = match (state, tt, lg) {
                 (Args, Group(gr), 0) if gr.delimiter() == Delimiter::Parenthesis => {
-                    if cfg!(debug_assertions) {
-                        for fl in gr.stream() {
-                            match fl {
-                                Punct(p) if p.as_char() == '!' => flags.panic = true,
-                                Ident(id) => match &id.to_string().to_lowercase()[..] {
-                                    "ns" | "sn" => {
-                                        flags.no_semnt = true;
-                                        flags.panic = true;
-                                    }
-                                    _ => (),
-                                },
+                    // the flag `!` is left for compatibility: the errors are always reported
+                    for fl in gr.stream() {
+                        if let Ident(id) = fl {
+                            match &id.to_string().to_lowercase()[..] {
+                                "ns" | "sn" => flags.no_semnt = true,
+                                "trace" => flags.trace = Some(id.span()),
                                 _ => (),
                             }
                         }
@@ -623,9 +3222,17 @@ impl Item {
         let mut m = MethIM::default();
         let mut args: Option<TokenStream> = None;
         let mut state = Start;
+        let mut is_async = false;
         for tt in ts {
             state = match (state, tt) {
-                (Start, Ident(id)) if id.to_string() == "fn" => m.prev_extend(Ident(id), Name),
+                (Start, Ident(id)) if id.to_string() == "fn" => {
+                    m.is_async = mem::take(&mut is_async);
+                    m.prev_extend(Ident(id), Name)
+                }
+                (Start, Ident(id)) if id.to_string() == "async" => {
+                    is_async = true;
+                    m.prev_extend(Ident(id), Start)
+                }
                 (Name, Ident(id)) => {
                     m.name = self.ident.as_ref().map_or(id.to_string(), |t| format!("{id}() {t}"));
                     args = None;
@@ -647,7 +3254,7 @@ impl Item {
                     if m.found_match(&gr) {
                         mmap.insert(
                             m.name.clone(), // v: bool = there is a generic
-                            args.take().map_or(false, |t| {
+                            args.take().is_some_and(|t| {
                                 t.into_iter()
                                     .any(|tr| matches!(tr, Ident(id) if id.to_string() == "impl"))
                             }),
@@ -669,6 +3276,7 @@ impl Item {
 #[derive(Default)]
 struct MethIM {
     name: String,
+    is_async: bool,
     prev_ts: TokenStream,
     body: TokenStream,
     dflt_arm: Option<Gr>,
@@ -739,10 +3347,10 @@ struct Var {
     methods: HashMap<String, VarMeth>,
 }
 impl Var {
-    fn vec(item: &mut Item) -> (Vec<Var>, String) {
+    fn vec(item: &mut Item) -> (Vec<Var>, Vec<(Span, String)>) {
         let mut iit = mem::take(&mut item.group).into_iter();
         let mut enm: Vec<Var> = Vec::new();
-        let mut err = String::new();
+        let mut err: Vec<(Span, String)> = Vec::new();
         let mut err_state = false;
         let dd = TokenStream::from_str("..").unwrap();
         let mut var = Var::default();
@@ -771,11 +3379,7 @@ impl Var {
                         _ => (),
                     },
                     Ident(id) => {
-                        if var.ident.is_none() {
-                            var.ident = Some(id.clone());
-                            item.group.extend(once(Ident(id)));
-                        // } else if id.to_string() == "fn" {
-                        } else {
+                        if let Some(var_ident) = &var.ident {
                             // method
                             let mut opt_tt = iit.next();
                             match opt_tt {
@@ -791,12 +3395,12 @@ impl Var {
                                 }
                                 _ => None,
                             };
-                            let in_enum_var =
-                                format!("in `enum {}::{}`", item.name, var.ident.as_ref().unwrap());
+                            let in_enum_var = format!("in `enum {}::{var_ident}`", item.name);
                             match opt_tt {
                                 Some(Group(block)) if block.delimiter() == Brace => {
                                     let name = (opt_trait.as_ref())
                                         .map_or(id.to_string(), |t| format!("{id}() {t}"));
+                                    let span = id.span();
                                     let m = VarMeth {
                                         ident: id,
                                         fields: var.fields.clone(),
@@ -804,26 +3408,38 @@ impl Var {
                                         opt_trait,
                                     };
                                     if var.methods.insert(name.clone(), m).is_some() {
-                                        err += &format!(
-                                            "\nRepetition of method name `{name}` \
+                                        err.push((
+                                            span,
+                                            format!(
+                                                "Repetition of method name `{name}` \
 {in_enum_var} (last arm-block used)"
-                                        );
+                                            ),
+                                        ));
                                     }
                                 }
                                 Some(tt2) => {
-                                    err += &format!(
-                                        "\nInvalid syntax in method `{id}` {in_enum_var} \
+                                    err.push((
+                                        tt2.span(),
+                                        format!(
+                                            "Invalid syntax in method `{id}` {in_enum_var} \
 - expected arm-block: `{{...}}`, found: `{tt2}`"
-                                    );
+                                        ),
+                                    ));
                                     err_state = true;
                                 }
                                 None => {
-                                    err += &format!(
-                                        "\nUnexpected end of macro on method`{id}` {in_enum_var}"
-                                    );
+                                    err.push((
+                                        id.span(),
+                                        format!(
+                                            "Unexpected end of macro on method `{id}` {in_enum_var}"
+                                        ),
+                                    ));
                                     err_state = true;
                                 }
                             };
+                        } else {
+                            var.ident = Some(id.clone());
+                            item.group.extend(once(Ident(id)));
                         }
                     }
                     Group(gr) if gr.delimiter() != Delimiter::Bracket => {
@@ -836,11 +3452,9 @@ impl Var {
                             _ => (),
                         }
                     }
-                    Punct(p) if p.as_char() == ',' => {
-                        if var.ident.is_some() {
-                            item.group.extend(once(Punct(p)));
-                            enm.push(mem::take(&mut var));
-                        }
+                    Punct(p) if p.as_char() == ',' && var.ident.is_some() => {
+                        item.group.extend(once(Punct(p)));
+                        enm.push(mem::take(&mut var));
                     }
                     _ => (),
                 }
@@ -910,7 +3524,7 @@ impl Var {
 /// **rust-analyzer**[^rust_analyzer] perfectly defines identifiers in all blocks. All hints, auto-completions and replacements in the IDE are processed in match-arm displayed in `enum` as if they were in their native match-block. Plus, the "inline macro" command works in the IDE, displaying the resulting code.
 ///
 /// [^rust_analyzer]: *rust-analyzer may not expand proc-macro when running under nightly or old rust edition.* In this case it is recommended to set in its settings: [`"rust-analyzer.server.extraEnv": { "RUSTUP_TOOLCHAIN": "stable" }`](https://rust-analyzer.github.io/manual.html#toolchain)
-/// 
+///
 /// ## Other features
 ///
 /// - You can also include `impl (Trait) for ...` blocks in a macro. The name of the `Trait` (without the path) is specified in the enum before the corresponding arm-block. Example with `Display` - below.
@@ -931,7 +3545,7 @@ impl Var {
 /// // @enum Shape<'a> {
 ///     Circle(f64, &'a str): (radius, mark)
 ///         zoom(scale)    { Shape::Circle(radius * scale, mark) }      // template change
-///         fmt(f) Display { write!(f, "{mark}(R: {radius:.1})") };     (_, mark) 
+///         fmt(f) Display { write!(f, "{mark}(R: {radius:.1})") };     (_, mark)
 ///         mark_obj(obj)  { format!("{} {}", mark, obj) };             (radius, _)
 ///         to_rect()      { *self = Shape::Rectangle { width: radius * 2., height: radius * 2.,} }
 ///     ,
@@ -966,8 +3580,9 @@ impl Var {
 /// ```
 /// - Debug flags. They can be placed through spaces in parentheses at the very beginning of the macro,   
 /// eg: `impl_match! { (ns ) `...
-///     - flag `ns` or `sn` in any case - replaces the semantic binding of the names of methods and traits in `enum` variants with a compilation error if they are incorrectly specified.
-///     - flag `!` - causes a compilation error in the same case, but without removing the semantic binding.
+///     - flag `ns` or `sn` in any case - removes the semantic binding of the names of methods and traits in `enum` variants: only the compilation errors of the macro itself remain.
+///     - flag `!` - has no effect: the macro always reports its errors as compilation errors.
+///     - flag `trace` (cargo feature `tracing`) - each method with a match-expression opens a `tracing` span named after the method and records in it the `enum` variant that served the call.
 ///
 /// ## [impl_match macro details](macro.impl_match.html#impl_match-macro-details)
 #[doc = include_str!("impl_match_details.md")]
@@ -976,18 +3591,22 @@ pub fn impl_match(input_ts: TokenStream) -> TokenStream {
     // std::fs::write("target/debug/input_ts.log", format!("{}\n\n{0:#?}", input_ts)).unwrap();
 
     let (mut items, mmap, flags) = Item::vec(input_ts);
-    let opt_enm_idx = (items.iter().enumerate().find_map(|(i, it)| it.no_def.then(|| i)))
-        .or_else(|| items.iter().enumerate().find_map(|(i, it)| it.it_enum.then(|| i)));
-    let ((mut enm, mut err), enm_i) =
-        opt_enm_idx.map_or(((Vec::new(), String::new()), None), |i| {
-            let enm_it = items.get_mut(i).unwrap();
-            (Var::vec(enm_it), enm_it.ident.take())
-        });
+    let opt_enm_idx = (items.iter().enumerate().find_map(|(i, it)| it.no_def.then_some(i)))
+        .or_else(|| items.iter().enumerate().find_map(|(i, it)| it.it_enum.then_some(i)));
+    let ((mut enm, mut err), enm_i) = opt_enm_idx.map_or(((Vec::new(), Vec::new()), None), |i| {
+        let enm_it = items.get_mut(i).unwrap();
+        (Var::vec(enm_it), enm_it.ident.take())
+    });
     let fat_arrow = TokenStream::from_str("=>").unwrap();
     let empty_gr = Gr::new(Brace, TokenStream::new());
     let dd = TokenStream::from_str("..").unwrap();
     let dd_gr = |g: &Gr| Gr::new(g.delimiter(), dd.clone());
 
+    let trace = flags.trace.is_some() && cfg!(feature = "tracing");
+    if let (Some(span), false) = (flags.trace, cfg!(feature = "tracing")) {
+        err.push((span, "flag `trace` requires the `tracing` feature of methods-enum".to_string()));
+    }
+
     let mut res_ts = TokenStream::new();
     for item in items.iter_mut() {
         res_ts.extend(mem::take(&mut item.prev_ts));
@@ -999,6 +3618,7 @@ pub fn impl_match(input_ts: TokenStream) -> TokenStream {
                 for mut m in mem::take(&mut item.methods) {
                     group.extend(m.prev_ts);
                     if !m.name.is_empty() {
+                        let span = m.body.clone().into_iter().next().unwrap().span();
                         let mut match_block = TokenStream::new();
                         for var in enm.iter_mut() {
                             let (fields, arm_block) = match var.methods.get_mut(&m.name) {
@@ -1020,10 +3640,45 @@ pub fn impl_match(input_ts: TokenStream) -> TokenStream {
                             ]));
                             match_block.extend(fields.map(Group));
                             match_block.extend(fat_arrow.clone());
+                            let arm_block = if trace {
+                                let var_i = var.ident.as_ref().unwrap();
+                                let mut arm_ts = spanned(
+                                    &format!("_span.record(\"variant\", \"{var_i}\");"),
+                                    var_i.span(),
+                                );
+                                arm_ts.extend(arm_block.stream());
+                                let mut arm_gr = Gr::new(Brace, arm_ts);
+                                arm_gr.set_span(arm_block.span());
+                                arm_gr
+                            } else {
+                                arm_block
+                            };
                             match_block.extend(once(Group(arm_block)));
                         }
                         m.body.extend(once(Group(Gr::new(Brace, match_block))).chain(m.tail));
-                        group.extend(once(Group(Gr::new(Brace, m.body))));
+                        let name = m.name.split("()").next().unwrap();
+                        let span_s = format!(
+                            "let _span = ::tracing::debug_span!(\"{name}\", \
+                            variant = ::tracing::field::Empty)"
+                        );
+                        let body = if trace && m.is_async {
+                            // the entered span must not be held across `.await`
+                            let mut body = spanned(&format!("{span_s};"), span);
+                            body.extend(spanned("::tracing::Instrument::instrument", span));
+                            let mut args = spanned("async", span);
+                            args.extend(once(Group(Gr::new(Brace, m.body))));
+                            args.extend(spanned(", ::core::clone::Clone::clone(&_span)", span));
+                            body.extend(once(Group(Gr::new(Delimiter::Parenthesis, args))));
+                            body.extend(spanned(".await", span));
+                            body
+                        } else if trace {
+                            let mut body = spanned(&format!("{span_s}.entered();"), span);
+                            body.extend(m.body);
+                            body
+                        } else {
+                            m.body
+                        };
+                        group.extend(once(Group(Gr::new(Brace, body))));
                     }
                 }
                 group
@@ -1033,95 +3688,95 @@ pub fn impl_match(input_ts: TokenStream) -> TokenStream {
     }
 
     // semantic+highlighting var methods / traits
-    if !flags.no_semnt {
-        if enm_i.is_some() {
-            let item_n = (items.iter())
-                .find_map(|it| (!it.it_enum && !it.name.is_empty()).then(|| it.name.clone()))
-                .unwrap_or_default();
-            let span = Span::call_site();
-            let item_ts = TokenStream::from_iter([
-                Ident(Idn::new(&item_n, span)),
-                Punct(Pn::new(':', Spacing::Joint)),
-                Punct(Pn::new(':', Spacing::Alone)),
-            ]);
-            let sm = Punct(Pn::new(';', Spacing::Alone));
-            let mut fn_ts = TokenStream::new();
-            if !item_n.is_empty() {
-                for var in enm.iter_mut() {
-                    for (k, m) in var.methods.iter_mut() {
-                        if !mmap.get(k).map_or(false, |&v| v) {
-                            fn_ts.extend(if let Some(trait_i) = m.opt_trait.take() {
-                                TokenStream::from_iter([
-                                    Punct(Pn::new('<', Spacing::Alone)),
-                                    Ident(Idn::new(&item_n, span)),
-                                    Ident(Idn::new("as", span)),
-                                    Ident(trait_i),
-                                    Punct(Pn::new('>', Spacing::Alone)),
-                                    Punct(Pn::new(':', Spacing::Joint)),
-                                    Punct(Pn::new(':', Spacing::Alone)),
-                                ])
-                            } else {
-                                item_ts.clone()
-                            });
-                            fn_ts.extend([Ident(m.ident.clone()), sm.clone()]);
-                        }
+    if !flags.no_semnt && enm_i.is_some() {
+        let item_n = (items.iter())
+            .find_map(|it| (!it.it_enum && !it.name.is_empty()).then(|| it.name.clone()))
+            .unwrap_or_default();
+        let span = Span::call_site();
+        let item_ts = TokenStream::from_iter([
+            Ident(Idn::new(&item_n, span)),
+            Punct(Pn::new(':', Spacing::Joint)),
+            Punct(Pn::new(':', Spacing::Alone)),
+        ]);
+        let sm = Punct(Pn::new(';', Spacing::Alone));
+        let mut fn_ts = TokenStream::new();
+        if !item_n.is_empty() {
+            for var in enm.iter_mut() {
+                for (k, m) in var.methods.iter_mut() {
+                    if !mmap.get(k).is_some_and(|&v| v) {
+                        fn_ts.extend(if let Some(trait_i) = m.opt_trait.take() {
+                            TokenStream::from_iter([
+                                Punct(Pn::new('<', Spacing::Alone)),
+                                Ident(Idn::new(&item_n, span)),
+                                Ident(Idn::new("as", span)),
+                                Ident(trait_i),
+                                Punct(Pn::new('>', Spacing::Alone)),
+                                Punct(Pn::new(':', Spacing::Joint)),
+                                Punct(Pn::new(':', Spacing::Alone)),
+                            ])
+                        } else {
+                            item_ts.clone()
+                        });
+                        fn_ts.extend([Ident(m.ident.clone()), sm.clone()]);
                     }
                 }
             }
-            if !fn_ts.is_empty() {
-                let mut hasher = DefaultHasher::new();
-                (item_n + "-" + mmap.keys().next().unwrap_or(&String::new())).hash(&mut hasher);
-                res_ts.extend(
-                    TokenStream::from_str(&format!(
-                        r##"#[allow(unused)]
-                            #[doc(hidden)]
-                            #[doc = " Semantic bindings for impl_match! macro"]
-                            mod _{}"##,
-                        hasher.finish()
-                    ))
-                    .unwrap(),
-                );
-                let mut mod_ts = TokenStream::from_str("use super::*; fn methods()").unwrap();
-                mod_ts.extend(once(Group(Gr::new(Brace, fn_ts))));
-                res_ts.extend(once(Group(Gr::new(Brace, mod_ts))));
-            }
+        }
+        if !fn_ts.is_empty() {
+            let mut hasher = DefaultHasher::new();
+            (item_n + "-" + mmap.keys().next().unwrap_or(&String::new())).hash(&mut hasher);
+            res_ts.extend(
+                TokenStream::from_str(&format!(
+                    r##"#[allow(unused)]
+                        #[doc(hidden)]
+                        #[doc = " Semantic bindings for impl_match! macro"]
+                        mod _{}"##,
+                    hasher.finish()
+                ))
+                .unwrap(),
+            );
+            let mut mod_ts = TokenStream::from_str("use super::*; fn methods()").unwrap();
+            mod_ts.extend(once(Group(Gr::new(Brace, fn_ts))));
+            res_ts.extend(once(Group(Gr::new(Brace, mod_ts))));
         }
     }
 
     // errors
-    if enm_i.is_some() {
+    if let Some(enm_n) = &enm_i {
         let mset: HashSet<String> = HashSet::from_iter(mmap.into_keys());
-        let enm_n = enm_i.as_ref().unwrap().to_string();
         for var in enm.iter() {
-            for name in var.methods.keys() {
+            let mut names: Vec<&String> = var.methods.keys().collect();
+            names.sort();
+            for name in names {
                 if !mset.contains(name) {
+                    let span = var.methods[name].ident.span();
                     let mut free_m: Vec<String> = mset
                         .difference(&HashSet::from_iter(var.methods.keys().cloned()))
                         .cloned()
                         .collect();
                     free_m.sort();
                     let enm_var = format!("`enum {enm_n}::{}`", var.ident.as_ref().unwrap());
-                    if free_m.is_empty() {
-                        err += &format!(
-                            "\nInvalid method `{name}` in {enm_var}:
+                    err.push((
+                        span,
+                        if free_m.is_empty() {
+                            format!(
+                                "Invalid method `{name}` in {enm_var}:
 `impl(-s)` contains no freely methods to implement `match{{...}}` from {enm_var}"
-                        )
-                    } else {
-                        err += &format!(
-                            "\nInvalid method name `{name}` in {enm_var} - expected{}: `{}`",
-                            if free_m.len() == 1 { "" } else { " one of" },
-                            free_m.join("`|`")
-                        )
-                    }
+                            )
+                        } else {
+                            format!(
+                                "Invalid method name `{name}` in {enm_var} - expected{}: `{}`",
+                                if free_m.len() == 1 { "" } else { " one of" },
+                                free_m.join("`|`")
+                            )
+                        },
+                    ));
                 };
             }
         }
     }
-    if !err.is_empty() {
-        eprintln!("\nErr in impl_match! macro:{err}\n");
-        if flags.panic {
-            panic!("Err in impl_match! macro:{err}");
-        }
+    for (span, msg) in err {
+        res_ts.extend(compile_error(&format!("impl_match!: {msg}"), span));
     }
 
     res_ts
@@ -0,0 +1,48 @@
+use std::error::Error;
+
+pub fn main() {
+    let mut c = Calc { acc: 0, log: Vec::new() };
+
+    c.apply(&|x| x * 2 + 1);
+    assert_eq!(c.acc, 1);
+    c.label("static");
+    c.sum_bytes([1, 2, 3, 4]);
+    assert_eq!(c.acc, 11);
+    let mut buf = [3, 1, 2];
+    c.sort(&mut buf);
+    assert_eq!(buf, [1, 2, 3]);
+    c.fail(Box::new(std::fmt::Error));
+    let s = "  x  ";
+    c.trim(&|s| s.trim(), &s);
+    assert_eq!(c.log, ["static", "an error occurred when formatting an argument", "x"]);
+    assert_eq!(c.pick(&|a, _| a), "first");
+}
+
+struct Calc {
+    acc: i32,
+    log: Vec<String>,
+}
+
+#[methods_enum::gen(Meth, run, Out)]
+impl Calc {
+    pub fn apply(&mut self, f: &dyn Fn(i32) -> i32);
+    pub fn label(&mut self, s: &'static str);
+    pub fn sum_bytes(&mut self, bytes: [u8; 4]);
+    pub fn sort(&mut self, v: &mut [u8]);
+    pub fn fail(&mut self, e: Box<dyn Error + Send>);
+    pub fn trim(&mut self, f: &dyn Fn(&str) -> &str, s: &&str);
+    pub fn pick(&mut self, f: &dyn for<'x> Fn(&'x str, &'x str) -> &'x str) -> &str;
+
+    fn run(&mut self, meth: Meth) -> Out {
+        match meth {
+            Meth::apply(f) => self.acc = f(self.acc),
+            Meth::label(s) => self.log.push(s.to_string()),
+            Meth::sum_bytes(bytes) => self.acc += bytes.iter().map(|&b| b as i32).sum::<i32>(),
+            Meth::sort(v) => v.sort(),
+            Meth::fail(e) => self.log.push(e.to_string()),
+            Meth::trim(f, s) => self.log.push(f(s).to_string()),
+            Meth::pick(f) => return Out::pick(f("first", "second")),
+        }
+        Out::Unit
+    }
+}
@@ -0,0 +1,91 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+pub fn main() {
+    let mut conn = Conn { state: State::Closed, sent: 0 };
+    assert_eq!(block_on(conn.send(b"ping")), Err("not connected".to_string()));
+    assert_eq!(block_on(conn.connect("127.0.0.1")), Ok(()));
+    assert!(matches!(&conn.state, State::Open(addr) if addr == "127.0.0.1"));
+    assert_eq!(block_on(conn.connect("127.0.0.1")), Err("already connected".to_string()));
+    assert_eq!(block_on(conn.send(b"ping")), Ok(4));
+    block_on(conn.close());
+    assert_eq!(conn.sent, 4);
+    assert!(matches!(conn.state, State::Closed));
+
+    // the futures of the generated methods can be sent to another thread
+    let fut = conn.send(b"x");
+    is_send(&fut);
+}
+
+fn is_send<T: Send>(_: &T) {}
+
+struct Noop;
+
+impl Wake for Noop {
+    fn wake(self: Arc<Self>) {}
+}
+
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = pin!(fut);
+    loop {
+        if let Poll::Ready(out) = fut.as_mut().poll(&mut cx) {
+            return out;
+        }
+    }
+}
+
+/// an operation that completes on the second poll
+async fn io<T>(val: T) -> T {
+    let mut polled = false;
+    std::future::poll_fn(|cx| {
+        if polled {
+            Poll::Ready(())
+        } else {
+            polled = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await;
+    val
+}
+
+enum State {
+    Closed,
+    Open(String),
+}
+
+struct Conn {
+    state: State,
+    sent: usize,
+}
+
+#[methods_enum::gen(Meth: run, Out)]
+impl Conn {
+    pub async fn connect(&mut self, addr: &str) -> Result<(), String>;
+    pub async fn send(&mut self, data: &[u8]) -> Result<usize, String>;
+    pub async fn close(&mut self);
+
+    async fn run(&mut self, meth: Meth<'_>) -> Out {
+        match (&self.state, meth) {
+            (State::Closed, Meth::connect(addr)) => {
+                self.state = State::Open(io(addr.to_string()).await);
+                Out::connect(Ok(()))
+            }
+            (State::Open(_), Meth::connect(_)) => Out::connect(Err("already connected".into())),
+            (State::Open(_), Meth::send(data)) => {
+                self.sent += io(data.len()).await;
+                Out::send(Ok(data.len()))
+            }
+            (State::Closed, Meth::send(_)) => Out::send(Err("not connected".into())),
+            (_, Meth::close()) => {
+                self.state = io(State::Closed).await;
+                Out::Unit
+            }
+        }
+    }
+}
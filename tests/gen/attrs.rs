@@ -0,0 +1,62 @@
+pub fn main() {
+    let mut reg = Registry { items: Vec::new() };
+    reg.add("a");
+    MethOwned::from(Meth::add("b")).apply(&mut reg);
+    assert_eq!(reg.count(), 2);
+    #[allow(deprecated)]
+    let len = reg.len();
+    assert_eq!(len, 2);
+    assert_eq!(reg.mode(), if cfg!(feature = "serde") { "serde" } else { "plain" });
+
+    #[cfg(feature = "serde")]
+    let mode = MethKind::mode_serde;
+    #[cfg(not(feature = "serde"))]
+    let mode = MethKind::mode_plain;
+    assert_eq!(MethKind::ALL, [MethKind::add, MethKind::count, MethKind::len, mode]);
+    assert_eq!(Meth::count().to_string(), "count()");
+    assert_eq!(Out::count(1).clone().into_count(), Some(1));
+}
+
+struct Registry {
+    items: Vec<String>,
+}
+
+#[methods_enum::gen(Meth: run, Out; owned, out_derive(Clone))]
+impl Registry {
+    /// Adds an item.
+    pub fn add(&mut self, item: &str);
+    #[must_use]
+    pub fn count(&mut self) -> usize;
+    #[deprecated(note = "use `count()`")]
+    #[inline]
+    pub fn len(&mut self) -> usize;
+    #[cfg(any())]
+    pub fn missing(&mut self, x: NotAType) -> NotAType;
+    #[cfg(feature = "serde")]
+    pub fn mode_serde(&mut self) -> &'static str;
+    #[cfg(not(feature = "serde"))]
+    pub fn mode_plain(&mut self) -> &'static str;
+
+    fn run(&mut self, meth: Meth) -> Out {
+        match meth {
+            Meth::add(item) => {
+                self.items.push(item.to_string());
+                Out::Unit
+            }
+            Meth::count() => Out::count(self.items.len()),
+            #[allow(deprecated)]
+            Meth::len() => Out::len(self.items.len()),
+            #[cfg(feature = "serde")]
+            Meth::mode_serde() => Out::mode_serde("serde"),
+            #[cfg(not(feature = "serde"))]
+            Meth::mode_plain() => Out::mode_plain("plain"),
+        }
+    }
+
+    pub fn mode(&mut self) -> &'static str {
+        #[cfg(feature = "serde")]
+        return self.mode_serde();
+        #[cfg(not(feature = "serde"))]
+        self.mode_plain()
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+pub fn main() {
+    let mut store = Store { data: HashMap::from([("a", "alpha".to_string())]), reads: 0 };
+    assert_eq!(store.get("a"), Ok("alpha".to_string()));
+    assert_eq!(store.get("b"), Err(Missing("b".to_string())));
+    assert_eq!(store.get_or_default("b"), "");
+    assert_eq!(store.get_len("a"), Ok(5));
+    assert_eq!(store.get_len("c"), Err("no key `c`".to_string()));
+    assert_eq!(store.reads, 5);
+
+    let mut num = Num(6);
+    assert_eq!(num.double(), 12);
+    assert_eq!(num.half(), 6.0);
+
+    let mut doc = Doc { text: "a b c".to_string(), locked: false };
+    assert_eq!(doc.text(), "a b c");
+    assert_eq!(doc.words(), 3);
+    assert_eq!(doc.try_text(), Ok("a b c"));
+    doc.locked = true;
+    assert_eq!(doc.try_text(), Err("locked: Unit".to_string()));
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Missing(String);
+
+struct Store {
+    data: HashMap<&'static str, String>,
+    reads: usize,
+}
+
+#[methods_enum::gen(Meth, run; bind = res)]
+impl Store {
+    pub fn get(&mut self, key: &str) -> Result<String, Missing>;
+    pub fn get_or_default(&mut self, key: &str) -> String {
+        res.unwrap_or_default()
+    }
+    pub fn get_len(&mut self, key: &str) -> Result<usize, String> {
+        res.map(|s| s.len()).map_err(|Missing(key)| format!("no key `{key}`"))
+    }
+
+    fn run(&mut self, meth: Meth) -> Result<String, Missing> {
+        self.reads += 1;
+        let key = match meth {
+            Meth::get(key) | Meth::get_or_default(key) | Meth::get_len(key) => key,
+        };
+        self.data.get(key).cloned().ok_or_else(|| Missing(key.to_string()))
+    }
+}
+
+// a binding in the signature without the option
+struct Num(i64);
+
+#[methods_enum::gen(Calc, eval)]
+impl Num {
+    pub fn double(&mut self) -> i64;
+    #[bind(v)]
+    pub fn half(&mut self) -> f64 {
+        v as f64 / 2.0
+    }
+
+    fn eval(&mut self, calc: Calc) -> i64 {
+        match calc {
+            Calc::double() | Calc::half() => self.0 * 2,
+        }
+    }
+}
+
+// with OutName: the option for the default bodies and a binding in the signature
+struct Doc {
+    text: String,
+    locked: bool,
+}
+
+#[methods_enum::gen(Op, run, Res; bind = res)]
+impl Doc {
+    pub fn text(&mut self) -> &str;
+    pub fn words(&mut self) -> usize {
+        match res {
+            Res::text(text) => text.split_whitespace().count(),
+            _ => 0,
+        }
+    }
+    #[bind(other)]
+    pub fn try_text(&mut self) -> Result<&str, String> {
+        let found = other.stype();
+        Err(format!("locked: {found}"))
+    }
+
+    fn run(&mut self, op: Op) -> Res {
+        match op {
+            Op::text() | Op::words() => Res::text(&self.text),
+            Op::try_text() if self.locked => Res::Unit,
+            Op::try_text() => Res::try_text(Ok(&self.text)),
+        }
+    }
+}
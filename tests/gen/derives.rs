@@ -0,0 +1,69 @@
+pub fn main() {
+    let mut calc = Calc { acc: 0 };
+    let queue = [Op::add(2), Op::mul(5), Op::add(1)];
+    for op in queue.iter().cloned() {
+        calc.run(op);
+    }
+    assert_eq!(calc.acc, 11);
+    assert_eq!(queue[1], Op::mul(5));
+    assert_eq!(calc.get(), 11);
+    let res = calc.res();
+    assert!(res.clone() == res);
+
+    let items = Items { items: vec![NoClone(1), NoClone(2)] };
+    let probe = NoClone(2);
+    let m = Look::has(&probe);
+    let (a, b) = (m, m);
+    assert!(items.look(a).into_has().unwrap());
+    let found = items.look(dup(&b));
+    assert!(matches!(found.clone(), Found::has(true)));
+    assert_eq!(items.first(), Some(&NoClone(1)));
+}
+
+fn dup<C: Clone>(c: &C) -> C {
+    c.clone()
+}
+
+struct Calc {
+    acc: i32,
+}
+
+#[methods_enum::gen(Op: run, Res; derive(Clone, PartialEq), out_derive(Clone, PartialEq), attr(must_use))]
+impl Calc {
+    pub fn add(&mut self, x: i32);
+    pub fn mul(&mut self, x: i32);
+    pub fn get(&mut self) -> i32;
+
+    fn run(&mut self, op: Op) -> Res {
+        match op {
+            Op::add(x) => self.acc += x,
+            Op::mul(x) => self.acc *= x,
+            Op::get() => return Res::get(self.acc),
+        }
+        Res::Unit
+    }
+
+    fn res(&self) -> Res {
+        Res::get(self.acc)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct NoClone(i32);
+
+struct Items<T> {
+    items: Vec<T>,
+}
+
+#[methods_enum::gen(Look: look, Found; derive(Clone, Copy), out_derive(Debug, Clone))]
+impl<T: PartialEq> Items<T> {
+    pub fn has(&self, item: &T) -> bool;
+    pub fn first(&self) -> Option<&T>;
+
+    fn look(&self, meth: Look<T>) -> Found<T> {
+        match meth {
+            Look::has(item) => Found::has(self.items.contains(item)),
+            Look::first() => Found::first(self.items.first()),
+        }
+    }
+}
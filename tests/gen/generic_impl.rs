@@ -0,0 +1,50 @@
+use std::fmt::Debug;
+
+pub fn main() {
+    let mut stack = Stack { items: vec![1, 2], limit: 3 };
+    assert_eq!(stack.push(3), Ok(3));
+    assert_eq!(stack.push(4), Err("limit 3 reached".to_string()));
+    assert_eq!(stack.peek(), Some(&3));
+    assert_eq!(stack.pop_if_eq(&3), Some(3));
+    assert_eq!(stack.pop_if_eq(&3), None);
+    assert_eq!(stack.len(), 2);
+
+    let mut named = Stack { items: vec!["a"], limit: 1 };
+    assert_eq!(named.push("b"), Err("limit 1 reached".to_string()));
+    assert_eq!(named.peek(), Some(&"a"));
+}
+
+pub struct Stack<T> {
+    items: Vec<T>,
+    limit: usize,
+}
+
+pub trait Item: Debug + PartialEq {}
+impl<T: Debug + PartialEq> Item for T {}
+
+#[methods_enum::gen(Meth: run, Out)]
+impl<'s, T: Item + 's> Stack<T>
+where
+    T: Clone,
+{
+    pub fn push(&mut self, item: T) -> Result<T, String>;
+    pub fn peek(&mut self) -> Option<&T>;
+    pub fn pop_if_eq(&mut self, item: &T) -> Option<T>;
+    pub fn len(&mut self) -> usize;
+
+    fn run(&mut self, meth: Meth<T>) -> Out<T> {
+        match meth {
+            Meth::push(item) if self.items.len() < self.limit => {
+                self.items.push(item.clone());
+                Out::push(Ok(item))
+            }
+            Meth::push(_) => Out::push(Err(format!("limit {} reached", self.limit))),
+            Meth::peek() => Out::peek(self.items.last()),
+            Meth::pop_if_eq(item) if self.items.last() == Some(item) => {
+                Out::pop_if_eq(self.items.pop())
+            }
+            Meth::pop_if_eq(_) => Out::pop_if_eq(None),
+            Meth::len() => Out::len(self.items.len()),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt::Display;
+
+pub fn main() {
+    let mut log = Log { lines: Vec::new(), muted: false };
+
+    log.push("start");
+    log.push_val(42);
+    log.push_joined(", ", &["a", "b"]);
+    log.push_with(&|x| x * 2, 21);
+    log.push_pair("key", &3.5);
+    assert_eq!(log.last(), Some("key: 3.5"));
+    log.push_sent(&7u8);
+    assert_eq!(log.first_longer("long line", "short"), Some("long line"));
+    log.mute();
+    log.push_val('x');
+    assert_eq!(log.lines, ["start", "42", "a, b", "42", "key: 3.5", "7", "long line"]);
+}
+
+struct Log {
+    lines: Vec<String>,
+    muted: bool,
+}
+
+#[methods_enum::gen(Meth, run)]
+impl Log {
+    fn push(&mut self, line: &str);
+    fn push_val<T: Display>(&mut self, val: T);
+    fn push_joined(&mut self, sep: impl Display, vals: &[&str]);
+    fn push_with<F>(&mut self, f: &F, x: i32)
+    where
+        F: Fn(i32) -> i32;
+    fn push_pair<'k, V: Display>(&mut self, key: &'k str, val: &V);
+    // auto traits and lifetimes can be added to the trait
+    fn push_sent<T>(&mut self, val: &T)
+    where
+        T: Display + Send + Sync + 'static;
+    fn last(&mut self) -> Option<&str>;
+    fn mute(&mut self);
+    fn first_longer<'b>(&mut self, a: &'b str, b: &'b str) -> Option<&str>;
+
+    #[rustfmt::skip]
+    fn run(&mut self, meth: Meth) -> Option<&str> {
+        if self.muted {
+            return None;
+        }
+        match meth {
+            Meth::push(line) => self.lines.push(line.to_string()),
+            Meth::push_val(val) => self.lines.push(val.to_string()),
+            Meth::push_joined(sep, vals) => self.lines.push(vals.join(&sep.to_string())),
+            Meth::push_with(f, x) => self.lines.push(f(x).to_string()),
+            Meth::push_pair(key, val) => self.lines.push(format!("{key}: {val}")),
+            Meth::push_sent(val) => self.lines.push(val.to_string()),
+            Meth::last() => return self.lines.last().map(|s| s.as_str()),
+            Meth::mute() => self.muted = true,
+            Meth::first_longer(a, b) => {
+                self.lines.push(if a.len() >= b.len() { a } else { b }.to_string());
+                return self.lines.last().map(|s| s.as_str());
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,104 @@
+pub fn main() {
+    let mut post = blog::Post::new();
+    post.add_text("I ate a salad for lunch today");
+    assert_eq!("", post.content());
+    assert_eq!("I ate a salad for lunch today", post.request_review().approve().content());
+
+    let mut lamp = Lamp { on: false, level: 0, log: Vec::new() };
+    lamp.set_level(3);
+    assert_eq!(lamp.level(), 0);
+    let mut lamp = lamp.switch();
+    lamp.set_level(3);
+    assert_eq!(lamp.level(), 3);
+    assert_eq!(lamp.log, ["set_level", "level", "switch", "set_level", "level"]);
+    assert_eq!(MethKind::ALL, [MethKind::set_level, MethKind::level]);
+    assert_eq!(MoveKind::ALL, [MoveKind::switch]);
+}
+
+mod blog {
+    enum State {
+        Draft,
+        PendingReview,
+        Published,
+    }
+
+    pub struct Post {
+        state: State,
+        content: String,
+    }
+
+    #[methods_enum::gen(Meth, run_methods; to(Move, run_move))]
+    impl Post {
+        #[to(Move)]
+        pub fn request_review(self) -> Post;
+        pub fn add_text(&mut self, text: &str);
+        #[to(Move)]
+        pub fn approve(self) -> Post;
+        pub fn content(&mut self) -> &str;
+
+        fn run_methods(&mut self, method: Meth) -> &str {
+            match self.state {
+                State::Draft => match method {
+                    Meth::add_text(text) => {
+                        self.content.push_str(text);
+                        ""
+                    }
+                    _ => "",
+                },
+                State::PendingReview => "",
+                State::Published => match method {
+                    Meth::content() => &self.content,
+                    _ => "",
+                },
+            }
+        }
+
+        fn run_move(mut self, method: Move) -> Post {
+            match (&self.state, method) {
+                (State::Draft, Move::request_review()) => self.state = State::PendingReview,
+                (State::PendingReview, Move::approve()) => self.state = State::Published,
+                _ => (),
+            }
+            self
+        }
+
+        pub fn new() -> Post {
+            Post { state: State::Draft, content: String::new() }
+        }
+    }
+}
+
+// a shared Out enum for both handlers
+struct Lamp {
+    on: bool,
+    level: u8,
+    log: Vec<&'static str>,
+}
+
+#[methods_enum::gen(Meth: run, Out; to(Move: run_move))]
+impl Lamp {
+    pub fn set_level(&mut self, level: u8);
+    pub fn level(&mut self) -> u8;
+    #[to(Move)]
+    pub fn switch(self) -> Lamp;
+
+    fn run(&mut self, meth: Meth) -> Out {
+        self.log.push(meth.name());
+        match meth {
+            Meth::set_level(level) if self.on => self.level = level,
+            Meth::set_level(_) => (),
+            Meth::level() => return Out::level(self.level),
+        }
+        Out::Unit
+    }
+
+    fn run_move(mut self, meth: Move) -> Out {
+        self.log.push(meth.name());
+        match meth {
+            Move::switch() => {
+                self.on = !self.on;
+                Out::switch(self)
+            }
+        }
+    }
+}
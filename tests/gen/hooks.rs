@@ -0,0 +1,143 @@
+use std::cell::{Cell, RefCell};
+
+pub fn main() {
+    let mut acc = Account { balance: 0, frozen: false, audit: Vec::new(), metrics: Vec::new() };
+    assert_eq!(acc.deposit(50), 50);
+    assert_eq!(acc.withdraw(80), Err("insufficient funds".to_string()));
+    acc.freeze();
+    assert_eq!(acc.deposit(10), 50);
+    assert_eq!(acc.audit, ["deposit(50)", "withdraw(80)", "freeze()", "deposit(10)"]);
+    assert_eq!(
+        acc.metrics,
+        ["deposit -> deposit", "withdraw -> withdraw", "freeze -> Unit", "deposit -> deposit"]
+    );
+
+    let req = Request::default().path("/a").header("x").path("/b");
+    assert_eq!(*req.log.borrow(), ["path", "header", "path"]);
+    assert_eq!(req.path, "/b");
+    assert_eq!(req.headers, ["x"]);
+
+    let mut c = Counter { n: 0, seen: Vec::new() };
+    c.inc();
+    assert_eq!(c.get(), 1);
+    assert_eq!(c.seen, [(CountKind::inc, 1), (CountKind::get, 1)]);
+
+    // `enum Found` borrows `self`: the handler and `after` take `&self`
+    let cat = Catalog { items: vec!["a".to_string(), "b".to_string()], lookups: Cell::new(0) };
+    assert_eq!(cat.first(), Some("a"));
+    assert_eq!(cat.size(), 2);
+    assert_eq!(cat.lookups.get(), 2);
+}
+
+struct Account {
+    balance: u64,
+    frozen: bool,
+    audit: Vec<String>,
+    metrics: Vec<String>,
+}
+
+#[methods_enum::gen(Meth: run, Out; before = audit, after = measure)]
+impl Account {
+    pub fn deposit(&mut self, amount: u64) -> u64;
+    pub fn withdraw(&mut self, amount: u64) -> Result<u64, String>;
+    pub fn freeze(&mut self);
+
+    fn run(&mut self, meth: Meth) -> Out {
+        match meth {
+            _ if self.frozen => Out::deposit(self.balance),
+            Meth::deposit(amount) => {
+                self.balance += amount;
+                Out::deposit(self.balance)
+            }
+            Meth::withdraw(amount) if amount > self.balance => {
+                Out::withdraw(Err("insufficient funds".to_string()))
+            }
+            Meth::withdraw(amount) => {
+                self.balance -= amount;
+                Out::withdraw(Ok(self.balance))
+            }
+            Meth::freeze() => {
+                self.frozen = true;
+                Out::Unit
+            }
+        }
+    }
+
+    fn audit(&mut self, meth: &Meth) {
+        self.audit.push(meth.to_string());
+    }
+
+    fn measure(&mut self, kind: MethKind, out: &Out) {
+        let variant = out.stype().split('(').next().unwrap();
+        self.metrics.push(format!("{} -> {variant}", kind.name()));
+    }
+}
+
+#[derive(Default)]
+struct Request {
+    path: String,
+    headers: Vec<String>,
+    log: RefCell<Vec<&'static str>>,
+}
+
+#[methods_enum::gen(Part, build; before = trace)]
+impl Request {
+    pub fn path(self, path: &str) -> Self;
+    pub fn header(self, header: &str) -> Self;
+
+    fn build(mut self, part: Part) -> Self {
+        match part {
+            Part::path(path) => self.path = path.to_string(),
+            Part::header(header) => self.headers.push(header.to_string()),
+        }
+        self
+    }
+
+    fn trace(&self, part: &Part) {
+        self.log.borrow_mut().push(part.name());
+    }
+}
+
+struct Counter {
+    n: u32,
+    seen: Vec<(CountKind, u32)>,
+}
+
+#[methods_enum::gen(Count, run; after = seen)]
+impl Counter {
+    pub fn inc(&mut self);
+    pub fn get(&mut self) -> u32;
+
+    fn run(&mut self, count: Count) -> u32 {
+        if let Count::inc() = count {
+            self.n += 1;
+        }
+        self.n
+    }
+
+    fn seen(&mut self, kind: CountKind, res: &u32) {
+        self.seen.push((kind, *res));
+    }
+}
+
+struct Catalog {
+    items: Vec<String>,
+    lookups: Cell<u32>,
+}
+
+#[methods_enum::gen(Query, find, Found; after = count)]
+impl Catalog {
+    pub fn first(&self) -> Option<&str>;
+    pub fn size(&self) -> usize;
+
+    fn find(&self, query: Query) -> Found {
+        match query {
+            Query::first() => Found::first(self.items.first().map(|s| s.as_str())),
+            Query::size() => Found::size(self.items.len()),
+        }
+    }
+
+    fn count(&self, _kind: QueryKind, _found: &Found) {
+        self.lookups.set(self.lookups.get() + 1);
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::HashSet;
+
+pub fn main() {
+    let mut post = Post { state: State::Draft, log: Vec::new() };
+
+    post.add_text("salad");
+    post.publish();
+    post.add_text("late");
+    post.move_to(2, 3);
+    assert_eq!(
+        post.log,
+        [
+            "add_text(\"late\") is not possible in state Published",
+            "move_to { x: 2, y: 3 } as move_to(2, 3)"
+        ]
+    );
+
+    assert_eq!(MethKind::ALL, [MethKind::add_text, MethKind::publish, MethKind::content]);
+    let names: Vec<_> = MethKind::ALL.iter().map(|k| k.name()).collect();
+    assert_eq!(names, ["add_text", "publish", "content"]);
+    assert_eq!(Meth::content().kind(), MethKind::content);
+    assert_eq!(Meth::add_text("x").name(), "add_text");
+    assert_eq!(Meth::publish().to_string(), "publish()");
+
+    let set: HashSet<CtlKind> = CtlKind::ALL.into_iter().collect();
+    assert!(set.contains(&CtlKind::move_to));
+}
+
+#[derive(Debug)]
+enum State {
+    Draft,
+    Published,
+}
+
+struct Post {
+    state: State,
+    log: Vec<String>,
+}
+
+#[methods_enum::gen(Meth: run)]
+impl Post {
+    fn add_text(&mut self, text: &str);
+    fn publish(&mut self);
+    fn content(&mut self);
+
+    fn run(&mut self, meth: Meth) {
+        match (&self.state, meth) {
+            (State::Draft, Meth::add_text(_)) => (),
+            (State::Draft, Meth::publish()) => self.state = State::Published,
+            (st, m) => self.log.push(format!("{m} is not possible in state {st:?}")),
+        }
+    }
+}
+
+#[methods_enum::gen(Ctl: control; named)]
+impl Post {
+    fn move_to(&mut self, x: i32, y: i32);
+
+    fn control(&mut self, ctl: Ctl) {
+        self.log.push(format!("{ctl:?} as {ctl}"));
+        match ctl.kind() {
+            CtlKind::move_to => (),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+pub fn main() {
+    let mut d = Dict { words: vec!["apple".to_string()], last: "" };
+
+    let key = String::from("banana");
+    let found = d.get_or(&key, "none");
+    assert_eq!(found, "none");
+    assert_eq!(d.get_or("apple", &key), "found");
+    assert_eq!(d.first(), "apple");
+    d.remember("static");
+    assert_eq!(d.last, "static");
+    let (a, b) = (String::from("a"), String::from("bb"));
+    assert_eq!(d.longer(&a, &b), "bb");
+
+    let text = String::from("src");
+    let mut p = Parser { src: &text, buf: String::new() };
+    p.push("buf");
+    let src = p.src();
+    assert_eq!((src, p.buf()), ("src", "buf"));
+}
+
+struct Dict {
+    words: Vec<String>,
+    last: &'static str,
+}
+
+#[methods_enum::gen(Meth, run, Out)]
+impl Dict {
+    pub fn get_or<'d>(&mut self, key: &str, default: &'d str) -> &'d str;
+    pub fn first(&mut self) -> &str;
+    pub fn remember(&mut self, s: &'static str);
+    pub fn longer<'x>(&mut self, a: &'x str, b: &'x str) -> &'x str;
+
+    fn run<'d, 'x>(&mut self, meth: Meth<'_, 'd, 'x>) -> Out<'_, 'd, 'x> {
+        match meth {
+            Meth::get_or(key, default) => {
+                Out::get_or(if self.words.iter().any(|w| w == key) { "found" } else { default })
+            }
+            Meth::first() => Out::first(&self.words[0]),
+            Meth::remember(s) => {
+                self.last = s;
+                Out::Unit
+            }
+            Meth::longer(a, b) => Out::longer(if a.len() >= b.len() { a } else { b }),
+        }
+    }
+}
+
+// the block uses `'a`: the elided lifetimes get another name
+struct Parser<'a> {
+    src: &'a str,
+    buf: String,
+}
+
+#[methods_enum::gen(Op, exec, Res)]
+impl<'a> Parser<'a> {
+    pub fn src(&mut self) -> &'a str;
+    pub fn buf(&mut self) -> &str;
+    pub fn push(&mut self, s: &str);
+
+    fn exec(&mut self, op: Op) -> Res<'_, 'a> {
+        match op {
+            Op::src() => Res::src(self.src),
+            Op::buf() => Res::buf(&self.buf),
+            Op::push(s) => {
+                self.buf.push_str(s);
+                Res::Unit
+            }
+        }
+    }
+}
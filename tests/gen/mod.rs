@@ -1,2 +1,25 @@
 pub mod state;
-pub mod escape_docout;
\ No newline at end of file
+pub mod escape_docout;
+pub mod generic_methods;
+pub mod generic_impl;
+pub mod trait_impl;
+pub mod named;
+pub mod kinds;
+pub mod out_conv;
+pub mod out_fallible;
+pub mod derives;
+pub mod owned;
+pub mod hooks;
+pub mod arg_types;
+pub mod lifetimes;
+pub mod receivers;
+pub mod parts;
+pub mod groups;
+pub mod attrs;
+pub mod async_fns;
+pub mod patterns;
+pub mod bind;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "tracing")]
+pub mod traced;
@@ -0,0 +1,50 @@
+use std::fmt::Display;
+
+pub fn main() {
+    let mut p = Point { x: 0, y: 0, log: Vec::new() };
+
+    p.move_to(1, 2);
+    p.shift(10, 20);
+    assert_eq!(p.pos(), (21, 12));
+    assert_eq!(p.label("p", 7), "p: (21, 12) #7");
+    p.reset();
+    assert_eq!(p.pos(), (0, 0));
+    assert_eq!(p.log, ["move_to", "shift", "reset"]);
+}
+
+struct Point {
+    x: i32,
+    y: i32,
+    log: Vec<&'static str>,
+}
+
+#[methods_enum::gen(Meth, run = Out; named)]
+impl Point {
+    fn move_to(&mut self, x: i32, y: i32);
+    fn shift(&mut self, dy: i32, dx: i32);
+    fn reset(&mut self);
+    fn pos(&mut self) -> (i32, i32);
+    fn label(&mut self, name: &str, n: impl Display) -> String;
+
+    fn run(&mut self, meth: Meth) -> Out {
+        match meth {
+            Meth::move_to { x, y } => {
+                (self.x, self.y) = (x, y);
+                self.log.push("move_to");
+            }
+            Meth::shift { dx, dy } => {
+                (self.x, self.y) = (self.x + dx, self.y + dy);
+                self.log.push("shift");
+            }
+            Meth::reset {} => {
+                (self.x, self.y) = (0, 0);
+                self.log.push("reset");
+            }
+            Meth::pos {} => return Out::pos((self.x, self.y)),
+            Meth::label { name, n } => {
+                return Out::label(format!("{name}: ({}, {}) #{n}", self.x, self.y))
+            }
+        }
+        Out::Unit
+    }
+}
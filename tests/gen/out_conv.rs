@@ -0,0 +1,114 @@
+use blog::{Post, State};
+
+pub fn main() {
+    let mut post = Post::new();
+
+    assert_eq!(post.add_text("I ate a salad for lunch today"), Ok(&State::Draft));
+    assert_eq!(post.approve(), Err("For State::Draft method 'approve' is not possible".into()));
+    assert_eq!(post.request_review(), Ok(&State::PendingReview));
+    assert_eq!(
+        post.content(),
+        Err("For State::PendingReview method 'content' is not possible".to_string())
+    );
+    assert_eq!(post.approve(), Ok(&State::Published));
+    assert_eq!(post.content(), Ok("I ate a salad for lunch today"));
+    assert_eq!(post.words(), 7);
+    assert_eq!(post.len(), 29);
+
+    blog::conversions();
+
+    let mut pool = Pool { items: vec![7] };
+    assert_eq!(*pool.boxed(), 7);
+    assert_eq!(pool.all(), [7]);
+    assert_eq!(pool.bytes(), [7]);
+    assert_eq!(pool.count(), 1);
+    assert_eq!(usize::try_from(Out2::<u8>::count(2)).ok(), Some(2));
+}
+
+// no conversions for the types of uncovered parameters and for the types that can be the same
+struct Pool<T> {
+    items: Vec<T>,
+}
+
+#[methods_enum::gen(Meth2, run, Out2)]
+impl<T: Clone + Into<u8>> Pool<T> {
+    pub fn boxed(&mut self) -> Box<T>;
+    pub fn all(&mut self) -> Vec<T>;
+    pub fn bytes(&mut self) -> Vec<u8>;
+    pub fn count(&mut self) -> usize;
+
+    fn run(&mut self, meth: Meth2) -> Out2<T> {
+        match meth {
+            Meth2::boxed() => Out2::boxed(Box::new(self.items[0].clone())),
+            Meth2::all() => Out2::all(self.items.clone()),
+            Meth2::bytes() => Out2::bytes(self.items.iter().cloned().map(Into::into).collect()),
+            Meth2::count() => self.items.len().into(),
+        }
+    }
+}
+
+mod blog {
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    pub enum State {
+        Draft,
+        PendingReview,
+        Published,
+    }
+
+    pub struct Post {
+        state: State,
+        content: String,
+    }
+
+    #[methods_enum::gen(Meth: run_methods, Out)]
+    impl Post {
+        pub fn add_text(&mut self, text: &str) -> Result<&State, String>;
+        pub fn request_review(&mut self) -> Result<&State, String>;
+        pub fn approve(&mut self) -> Result<&State, String>;
+        #[rustfmt::skip]
+        pub fn content(&mut self) -> Result<&str, String> {
+            Result::<&State, String>::try_from(_out).ok().unwrap().map(|_| "")
+        }
+        pub fn words(&mut self) -> usize;
+        pub fn len(&mut self) -> u32;
+
+        fn run_methods(&mut self, method: Meth) -> Out {
+            match (self.state, method) {
+                (State::Draft, Meth::add_text(text)) => {
+                    self.content.push_str(text);
+                    Ok(&self.state).into()
+                }
+                (State::Draft, Meth::request_review()) => {
+                    self.state = State::PendingReview;
+                    Ok(&self.state).into()
+                }
+                (State::PendingReview, Meth::approve()) => {
+                    self.state = State::Published;
+                    Ok(&self.state).into()
+                }
+                (State::Published, Meth::content()) => Out::content(Ok(&self.content)),
+                (_, Meth::words()) => self.content.split(' ').count().into(),
+                (_, Meth::len()) => (self.content.len() as u32).into(),
+                (st, m) => {
+                    Err::<&State, _>(format!("For State::{st:?} method '{m:?}' is not possible"))
+                        .into()
+                }
+            }
+        }
+
+        pub fn new() -> Post {
+            Post { state: State::Draft, content: String::new() }
+        }
+    }
+
+    pub fn conversions() {
+        assert!(matches!(Out::from(Err::<&State, _>("e".to_string())), Out::add_text(Err(_))));
+        assert_eq!(Out::words(3).into_words(), Some(3));
+        assert_eq!(Out::words(3).into_len(), None);
+        assert_eq!(usize::try_from(Out::words(3)).ok(), Some(3));
+        assert!(matches!(u32::try_from(Out::words(3)), Err(Out::words(3))));
+        let r = Result::<&State, String>::try_from(Out::approve(Ok(&State::Draft)));
+        assert!(matches!(r, Ok(Ok(State::Draft))));
+        assert_eq!(Out::content(Ok("text")).into_content(), Some(Ok("text")));
+    }
+}
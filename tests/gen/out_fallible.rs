@@ -0,0 +1,67 @@
+pub fn main() {
+    let mut w = Worker { jobs: vec![3, 5], broken: false };
+
+    assert_eq!(w.next_job(), Ok(Some(5)));
+    assert_eq!(w.count(), Ok(1));
+    w.break_down();
+    let err = w.next_job().unwrap_err();
+    assert_eq!(err, OutMismatch { method: "next_job", found: "Unit" });
+    assert_eq!(err.to_string(), "Type mismatch in the next_job() method: found- Out::Unit");
+    let err: Box<dyn std::error::Error> = Box::new(w.count().unwrap_err());
+    assert_eq!(err.to_string(), "Type mismatch in the count() method: found- Out::Unit");
+    assert_eq!(w.label(), "broken");
+
+    assert_eq!(w.peek(), 3);
+    assert_eq!(w.total(), 3);
+    w.jobs.clear();
+    assert_eq!(w.peek(), 0);
+    assert_eq!(w.total(), 0);
+}
+
+struct Worker {
+    jobs: Vec<u32>,
+    broken: bool,
+}
+
+#[methods_enum::gen(Meth: run = Out; try)]
+impl Worker {
+    fn next_job(&mut self) -> Option<u32>;
+    fn count(&mut self) -> usize;
+    fn break_down(&mut self);
+    #[rustfmt::skip]
+    fn label(&mut self) -> &str { "broken" }
+
+    fn run(&mut self, meth: Meth) -> Out {
+        if self.broken {
+            return Out::Unit;
+        }
+        match meth {
+            Meth::next_job() => Out::next_job(self.jobs.pop()),
+            Meth::count() => Out::count(self.jobs.len()),
+            Meth::break_down() => {
+                self.broken = true;
+                Out::Unit
+            }
+            Meth::label() => Out::label("ok"),
+        }
+    }
+}
+
+#[methods_enum::gen(Peek: peek_run, PeekOut; fallback = on_mismatch)]
+impl Worker {
+    fn peek(&self) -> u32;
+    fn total(&self) -> u32;
+
+    fn peek_run(&self, meth: Peek) -> PeekOut {
+        match (meth, self.jobs.first()) {
+            (_, None) => PeekOut::Unit,
+            (Peek::peek(), Some(&j)) => PeekOut::peek(j),
+            (Peek::total(), _) => PeekOut::total(self.jobs.iter().sum()),
+        }
+    }
+
+    fn on_mismatch<T: Default>(method: &str, out: PeekOut) -> T {
+        assert!(matches!(out, PeekOut::Unit), "{method}");
+        T::default()
+    }
+}
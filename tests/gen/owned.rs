@@ -0,0 +1,116 @@
+pub fn main() {
+    let mut ed = Editor::default();
+    ed.insert("hello");
+    ed.extend(&[" big".to_string(), " world".to_string()]);
+    ed.set_style(&Style { bold: true });
+    let mut buf = vec![b'!'];
+    ed.flush(&mut buf);
+    assert_eq!(ed.count(3), 3);
+    assert_eq!(ed.text, "hello big world");
+    assert_eq!(buf, b"!hello big world");
+
+    let log = std::mem::take(&mut ed.log);
+    assert_eq!(log.len(), 5);
+    let mut copy = Editor::default();
+    for cmd in log {
+        cmd.apply(&mut copy);
+    }
+    assert_eq!((copy.text, copy.style), (ed.text, ed.style));
+    assert_eq!(copy.log.len(), 5);
+
+    let mut queue: Vec<CmdOwned> = Vec::new();
+    let from = String::from("big");
+    {
+        let to = String::from("small");
+        queue.push(Cmd::replace(&from, &to).into());
+        queue.push(Cmd::insert(&to).into());
+    }
+    drop(from);
+    let mut ed = Editor { text: "big world".to_string(), ..Editor::default() };
+    for cmd in queue {
+        cmd.apply(&mut ed);
+    }
+    assert_eq!(ed.text, "small worldsmall");
+
+    let mut bag = Bag { items: Vec::new() };
+    let queue: Vec<OpOwned<Token>> = vec![
+        Op::add { item: &Token(1) }.into(),
+        Op::add_all { items: &[Token(2), Token(3)] }.into(),
+    ];
+    for op in queue {
+        op.apply(&mut bag);
+    }
+    assert_eq!(bag.items, [Token(1), Token(2), Token(3)]);
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    bold: bool,
+}
+
+#[derive(Default)]
+struct Editor {
+    text: String,
+    style: Style,
+    log: Vec<CmdOwned>,
+}
+
+#[methods_enum::gen(Cmd: run; owned)]
+impl Editor {
+    pub fn insert(&mut self, text: &str);
+    pub fn extend(&mut self, lines: &[String]);
+    pub fn set_style(&mut self, style: &Style);
+    pub fn flush(&mut self, buf: &mut Vec<u8>);
+    pub fn count(&mut self, n: usize) -> usize;
+    pub fn replace(&mut self, from: &str, to: &str);
+
+    fn run(&mut self, cmd: Cmd) -> usize {
+        let n = match &cmd {
+            Cmd::insert(text) => {
+                self.text.push_str(text);
+                0
+            }
+            Cmd::extend(lines) => {
+                self.text.push_str(&lines.concat());
+                0
+            }
+            Cmd::set_style(style) => {
+                self.style = (*style).clone();
+                0
+            }
+            Cmd::flush(_) => 0,
+            Cmd::count(n) => *n,
+            Cmd::replace(from, to) => {
+                self.text = self.text.replace(from, to);
+                0
+            }
+        };
+        if let Cmd::flush(buf) = cmd {
+            buf.extend(self.text.as_bytes());
+            self.log.push(CmdOwned::flush(Vec::new()));
+        } else {
+            self.log.push(cmd.into());
+        }
+        n
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token(u32);
+
+struct Bag<T> {
+    items: Vec<T>,
+}
+
+#[methods_enum::gen(Op, run; owned, named)]
+impl<T: Clone> Bag<T> {
+    pub fn add(&mut self, item: &T);
+    pub fn add_all(&mut self, items: &[T]);
+
+    fn run(&mut self, op: Op<T>) {
+        match op {
+            Op::add { item } => self.items.push(item.clone()),
+            Op::add_all { items } => self.items.extend_from_slice(items),
+        }
+    }
+}
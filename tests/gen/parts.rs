@@ -0,0 +1,82 @@
+pub fn main() {
+    let mut doc = Doc::default();
+    doc.write("abc");
+    assert_eq!(doc.size(), 3);
+    assert_eq!(doc.status(), "open");
+    doc.lock();
+    doc.write("d");
+    assert_eq!(doc.text(), "abc");
+    assert_eq!(doc.status(), "locked");
+    assert_eq!(MethKind::ALL.map(|k| k.name()), ["write", "lock", "size", "text", "status"]);
+
+    let mut c = Counter(0);
+    c.add(5);
+    assert_eq!(c.get(), 5);
+}
+
+#[derive(Default)]
+pub struct Doc {
+    text: String,
+    locked: bool,
+}
+
+mod edit {
+    // the header of a part is not used: the block is moved into the `#[gen]` block
+    #[methods_enum::gen_part(Meth, edit_part)]
+    impl Doc {
+        pub fn write(&mut self, s: &str);
+        pub fn lock(&mut self);
+    }
+}
+
+#[methods_enum::gen_part(Meth, query_part)]
+impl Doc {
+    pub fn size(&mut self) -> usize;
+    pub fn text(&mut self) -> &str;
+}
+
+#[methods_enum::gen(Meth, run, Out; parts(edit::edit_part, query_part))]
+impl Doc {
+    pub fn status(&mut self) -> &'static str;
+
+    fn run(&mut self, meth: Meth) -> Out {
+        match meth {
+            Meth::write(_) | Meth::lock() if self.locked => Out::Unit,
+            Meth::write(s) => {
+                self.text.push_str(s);
+                Out::Unit
+            }
+            Meth::lock() => {
+                self.locked = true;
+                Out::Unit
+            }
+            Meth::size() => Out::size(self.text.len()),
+            Meth::text() => Out::text(&self.text),
+            Meth::status() => Out::status(if self.locked { "locked" } else { "open" }),
+        }
+    }
+}
+
+// the crate through a re-export
+mod reexport {
+    pub use methods_enum as me;
+}
+
+pub struct Counter(u32);
+
+#[reexport::me::gen_part(Op, add_part; crate = crate::gen::parts::reexport::me)]
+impl Counter {
+    pub fn add(&mut self, n: u32);
+}
+
+#[reexport::me::gen(Op, run; parts(add_part))]
+impl Counter {
+    pub fn get(&mut self) -> u32;
+
+    fn run(&mut self, op: Op) -> u32 {
+        if let Op::add(n) = op {
+            self.0 += n;
+        }
+        self.0
+    }
+}
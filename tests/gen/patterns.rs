@@ -0,0 +1,54 @@
+pub fn main() {
+    let mut canvas = Canvas { pos: (0, 0), log: Vec::new() };
+    canvas.move_to((3, 4));
+    assert_eq!(canvas.pos, (3, 4));
+    canvas.shift(Point { x: 1, y: -1 });
+    assert_eq!(canvas.pos, (4, 3));
+    canvas.offset(&(2, 2));
+    assert_eq!(canvas.pos, (6, 5));
+    canvas.write(String::from("hi"));
+    canvas.span(&[1, 2], Size(5));
+    assert_eq!(canvas.log, ["hi!", "[1, 2]x5"]);
+    assert_eq!(
+        Meth::move_to { x_y: (1, 2) }.to_string(),
+        "move_to((1, 2))",
+        "the variant of a destructured argument takes its type"
+    );
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug)]
+pub struct Size(usize);
+
+struct Canvas {
+    pos: (i32, i32),
+    log: Vec<String>,
+}
+
+#[methods_enum::gen(Meth: run; named)]
+impl Canvas {
+    pub fn move_to(&mut self, (x, y): (i32, i32));
+    pub fn shift(&mut self, Point { x: dx, y: dy }: Point);
+    // a reference pattern: the `mut` of the binding is dropped, `&` is kept
+    pub fn offset(&mut self, &(dx, mut dy): &(i32, i32));
+    pub fn write(&mut self, mut text: String);
+    pub fn span(&mut self, items: &[u8], Size(mut n): Size);
+
+    fn run(&mut self, meth: Meth) {
+        match meth {
+            Meth::move_to { x_y } => self.pos = x_y,
+            Meth::shift { dx_dy: Point { x, y } } => self.pos = (self.pos.0 + x, self.pos.1 + y),
+            Meth::offset { dx_dy: (x, y) } => self.pos = (self.pos.0 + x, self.pos.1 + y),
+            Meth::write { mut text } => {
+                text.push('!');
+                self.log.push(text)
+            }
+            Meth::span { items, n } => self.log.push(format!("{items:?}x{}", n.0)),
+        }
+    }
+}
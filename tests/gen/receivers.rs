@@ -0,0 +1,79 @@
+use std::pin::Pin;
+use std::rc::Rc;
+
+pub fn main() {
+    let actor = Box::new(Actor { state: 0 });
+    let actor = actor.start().step(2).step(3);
+    assert_eq!(actor.state, 6);
+    assert_eq!(actor.stop(), 6);
+
+    let mut timer = Timer { ticks: 0 };
+    let mut pinned = Pin::new(&mut timer);
+    pinned.as_mut().tick();
+    pinned.as_mut().tick();
+    assert_eq!(pinned.as_mut().elapsed(), 2);
+
+    let cfg = Rc::new(Config { name: "main".to_string() });
+    assert_eq!(cfg.clone().describe(), "config main");
+    assert_eq!(Rc::strong_count(&cfg), 1);
+}
+
+struct Actor {
+    state: u32,
+}
+
+#[methods_enum::gen(Msg, handle, Reply)]
+impl Actor {
+    pub fn start(self: Box<Self>) -> Box<Self>;
+    pub fn step(self: Box<Self>, n: u32) -> Box<Self>;
+    pub fn stop(self: Box<Self>) -> u32;
+
+    fn handle(mut self: Box<Self>, msg: Msg) -> Reply {
+        match msg {
+            Msg::start() => {
+                self.state = 1;
+                Reply::start(self)
+            }
+            Msg::step(n) => {
+                self.state += n;
+                Reply::step(self)
+            }
+            Msg::stop() => Reply::stop(self.state),
+        }
+    }
+}
+
+struct Timer {
+    ticks: u64,
+}
+
+#[methods_enum::gen(Poll, poll, Ready)]
+impl Timer {
+    pub fn tick(self: Pin<&mut Self>);
+    pub fn elapsed(self: Pin<&mut Self>) -> u64;
+
+    fn poll(mut self: Pin<&mut Self>, p: Poll) -> Ready {
+        match p {
+            Poll::tick() => {
+                self.ticks += 1;
+                Ready::Unit
+            }
+            Poll::elapsed() => Ready::elapsed(self.ticks),
+        }
+    }
+}
+
+struct Config {
+    name: String,
+}
+
+#[methods_enum::gen(Query, query)]
+impl Config {
+    pub fn describe(self: Rc<Self>) -> String;
+
+    fn query(self: Rc<Self>, q: Query) -> String {
+        match q {
+            Query::describe() => format!("config {}", self.name),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+pub fn main() {
+    let mut post = Post { text: String::new(), approved: false };
+
+    let json = serde_json::to_string(&Meth::add_text { text: "salad" }).unwrap();
+    assert_eq!(json, r#"{"add_text":{"text":"salad"}}"#);
+    let meth: Meth = serde_json::from_str(&json).unwrap();
+    let out = post.run(meth);
+    assert_eq!(serde_json::to_string(&out).unwrap(), r#"{"add_text":5}"#);
+    let out: Out = serde_json::from_str(r#"{"content":"salad"}"#).unwrap();
+    assert!(matches!(out, Out::content("salad")));
+    assert_eq!(serde_json::to_string(&Out::Unit).unwrap(), r#""Unit""#);
+
+    let wire =
+        serde_json::to_string(&CmdOwned::from(Cmd::rename { text: "lunch", upper: true })).unwrap();
+    assert_eq!(wire, r#"{"rename":{"text":"lunch","upper":true}}"#);
+    let cmd: CmdOwned = serde_json::from_str(&wire).unwrap();
+    cmd.apply(&mut post);
+    assert_eq!(post.text, "LUNCH");
+    let cmd: CmdOwned = serde_json::from_str(r#"{"approve":{}}"#).unwrap();
+    cmd.apply(&mut post);
+    assert!(post.approved);
+}
+
+struct Post {
+    text: String,
+    approved: bool,
+}
+
+#[methods_enum::gen(Meth: run = Out; serde)]
+impl Post {
+    pub fn add_text(&mut self, text: &str) -> usize;
+    pub fn content(&mut self) -> &str;
+
+    fn run(&mut self, meth: Meth) -> Out {
+        match meth {
+            Meth::add_text { text } => {
+                self.text.push_str(text);
+                Out::add_text(text.len())
+            }
+            Meth::content {} => Out::content(&self.text),
+        }
+    }
+}
+
+#[methods_enum::gen(Cmd, exec; owned, serde)]
+impl Post {
+    pub fn rename(&mut self, text: &str, upper: bool);
+    pub fn approve(&mut self);
+
+    fn exec(&mut self, cmd: Cmd) {
+        match cmd {
+            Cmd::rename { text, upper: true } => self.text = text.to_uppercase(),
+            Cmd::rename { text, .. } => self.text = text.to_string(),
+            Cmd::approve {} => self.approved = true,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+pub fn main() {
+    let spans = collect(|| {
+        let mut post = Post { text: String::new(), published: false };
+        post.add_text("salad");
+        post.publish();
+        assert_eq!(post.content(), "salad");
+    });
+    assert_eq!(
+        spans,
+        [("add_text", "add_text"), ("publish", "publish"), ("content", "content")]
+            .map(|(n, v)| (n, v.to_string()))
+    );
+
+    // the span of an `async` method instruments the call of the handler
+    let spans = collect(|| {
+        let mut queue = Queue { items: Vec::new() };
+        crate::gen::async_fns::block_on(queue.push(1));
+        assert_eq!(queue.items, [1]);
+    });
+    assert_eq!(spans, [("push", "push".to_string())]);
+
+    // without the option `trace` the methods have no spans
+    let spans = collect(|| {
+        let mut draft = Draft { text: String::new() };
+        draft.add_text("soup");
+        assert_eq!(draft.text, "soup");
+    });
+    assert!(spans.is_empty());
+}
+
+struct Post {
+    text: String,
+    published: bool,
+}
+
+#[methods_enum::gen(Meth, run, Out; trace)]
+impl Post {
+    pub fn add_text(&mut self, text: &str);
+    pub fn publish(&mut self);
+    pub fn content(&mut self) -> &str;
+
+    fn run(&mut self, meth: Meth) -> Out {
+        match meth {
+            Meth::add_text(text) => self.text.push_str(text),
+            Meth::publish() => self.published = true,
+            Meth::content() => return Out::content(if self.published { &self.text } else { "" }),
+        }
+        Out::Unit
+    }
+}
+
+struct Queue {
+    items: Vec<u8>,
+}
+
+#[methods_enum::gen(QueueMeth, run; trace)]
+impl Queue {
+    pub async fn push(&mut self, x: u8);
+
+    async fn run(&mut self, meth: QueueMeth) {
+        match meth {
+            QueueMeth::push(x) => self.items.push(x),
+        }
+    }
+}
+
+struct Draft {
+    text: String,
+}
+
+#[methods_enum::gen(DraftMeth, run)]
+impl Draft {
+    pub fn add_text(&mut self, text: &str);
+
+    fn run(&mut self, meth: DraftMeth) {
+        let DraftMeth::add_text(text) = meth;
+        self.text.push_str(text);
+    }
+}
+
+/// `(name, variant)` of the spans opened while `f` runs
+pub fn collect(f: impl FnOnce()) -> Vec<(&'static str, String)> {
+    let spans = Spans::default();
+    tracing::subscriber::with_default(spans.clone(), f);
+    let res = spans.0.lock().unwrap().clone();
+    res
+}
+
+#[derive(Clone, Default)]
+struct Spans(Arc<Mutex<Vec<(&'static str, String)>>>);
+
+struct Variant<'a>(&'a mut String);
+
+impl Visit for Variant<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "variant" {
+            *self.0 = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "variant" {
+            *self.0 = format!("{value:?}");
+        }
+    }
+}
+
+impl Subscriber for Spans {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut spans = self.0.lock().unwrap();
+        let mut variant = String::new();
+        span.record(&mut Variant(&mut variant));
+        spans.push((span.metadata().name(), variant));
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = self.0.lock().unwrap();
+        values.record(&mut Variant(&mut spans[span.into_u64() as usize - 1].1));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, _: &Event<'_>) {}
+    fn enter(&self, _: &Id) {}
+    fn exit(&self, _: &Id) {}
+}
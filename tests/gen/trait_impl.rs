@@ -0,0 +1,72 @@
+use blog::{Blog, Post};
+
+pub fn main() {
+    let mut post = Post::new();
+
+    post.add_text("I ate a salad for lunch today");
+    assert_eq!("", post.content());
+    post.request_review();
+    post.approve();
+    assert_eq!("I ate a salad for lunch today", post.content());
+    post.replace(String::from("I ate a salad"));
+    assert_eq!("I ate a salad", post.content());
+}
+
+mod blog {
+    pub trait Blog {
+        type Text;
+        fn add_text(&mut self, text: &str);
+        fn replace(&mut self, text: Self::Text);
+        fn request_review(&mut self);
+        fn approve(&mut self);
+        fn content(&mut self) -> &str;
+    }
+
+    enum State {
+        Draft,
+        PendingReview,
+        Published,
+    }
+
+    pub struct Post {
+        state: State,
+        content: String,
+    }
+
+    #[methods_enum::gen(Meth, run_methods)]
+    impl Blog for Post {
+        type Text = String;
+
+        fn add_text(&mut self, text: &str);
+        fn replace(&mut self, text: Self::Text);
+        fn request_review(&mut self);
+        fn approve(&mut self);
+        fn content(&mut self) -> &str;
+    }
+
+    impl Post {
+        #[rustfmt::skip]
+        fn run_methods(&mut self, method: Meth) -> &str {
+            match self.state {
+                State::Draft => match method {
+                    Meth::add_text(text) => { self.content.push_str(text); "" }
+                    Meth::request_review() => { self.state = State::PendingReview; "" }
+                    _ => "",
+                },
+                State::PendingReview => match method {
+                    Meth::approve() => { self.state = State::Published; "" }
+                    _ => "",
+                },
+                State::Published => match method {
+                    Meth::content() => &self.content,
+                    Meth::replace(text) => { self.content = text; "" }
+                    _ => "",
+                },
+            }
+        }
+
+        pub fn new() -> Post {
+            Post { state: State::Draft, content: String::new() }
+        }
+    }
+}
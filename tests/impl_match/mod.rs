@@ -1,3 +1,5 @@
 pub mod state;
 pub mod polymorphism;
-pub mod polymorphism_lftm;
\ No newline at end of file
+pub mod polymorphism_lftm;
+#[cfg(feature = "tracing")]
+pub mod traced;
\ No newline at end of file
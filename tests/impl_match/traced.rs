@@ -0,0 +1,97 @@
+use crate::gen::async_fns::block_on;
+use crate::gen::traced::collect;
+use std::task::Poll;
+
+pub fn main() {
+    let spans = collect(|| {
+        let mut door = Door { state: State::Closed, opened: 0 };
+        door.open();
+        door.open();
+        door.close();
+        assert_eq!(door.opened, 1);
+    });
+    assert_eq!(
+        spans,
+        [("open", "Closed"), ("open", "Open"), ("close", "Open")].map(|(n, v)| (n, v.to_string()))
+    );
+
+    // the span of an `async` method instruments its body: the future stays `Send`
+    let spans = collect(|| {
+        let mut door = Door { state: State::Closed, opened: 0 };
+        let knock = door.knock();
+        assert_send(&knock);
+        assert_eq!(block_on(knock), 1);
+    });
+    assert_eq!(spans, [("knock", "Closed".to_string())]);
+
+    // without the flag `trace` the methods have no spans
+    let spans = collect(|| {
+        let mut lamp = untraced::Lamp { state: untraced::Light::Off };
+        lamp.toggle();
+        assert!(matches!(lamp.state, untraced::Light::On));
+    });
+    assert!(spans.is_empty());
+}
+
+fn assert_send<T: Send>(_: &T) {}
+
+/// completes on the second poll
+async fn yield_now() {
+    let mut polled = false;
+    std::future::poll_fn(|cx| {
+        if polled {
+            Poll::Ready(())
+        } else {
+            polled = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+struct Door {
+    state: State,
+    opened: u32,
+}
+
+methods_enum::impl_match! { (trace)
+impl Door {
+    pub fn open(&mut self)              ~{ match self.state {} }
+    pub fn close(&mut self)             ~{ match self.state {} }
+    pub async fn knock(&mut self) -> u8 ~{ match self.state {} }
+}
+
+enum State {
+    Closed:
+        open() {
+            self.opened += 1;
+            self.state = State::Open
+        }
+        knock() {
+            yield_now().await;
+            1
+        }
+    ,
+    Open:
+        close() { self.state = State::Closed }
+        knock() { 0 }
+}
+} // impl_match!
+
+mod untraced {
+    pub struct Lamp {
+        pub state: Light,
+    }
+
+    methods_enum::impl_match! {
+    impl Lamp {
+        pub fn toggle(&mut self) ~{ match self.state {} }
+    }
+
+    pub enum Light {
+        Off: toggle() { self.state = Light::On },
+        On: toggle() { self.state = Light::Off }
+    }
+    } // impl_match!
+}
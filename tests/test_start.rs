@@ -1,4 +1,7 @@
 // #![allow(unused)]
+// the examples keep the style of the documentation
+#![allow(mismatched_lifetime_syntaxes)]
+#![allow(clippy::single_match, clippy::match_single_binding, clippy::disallowed_names)]
 
 mod gen;
 mod impl_match;
@@ -20,6 +23,29 @@ fn test_main() {
     gen::state::state_machine::test();
 
     gen::escape_docout::main();
+    gen::generic_methods::main();
+    gen::generic_impl::main();
+    gen::trait_impl::main();
+    gen::named::main();
+    gen::kinds::main();
+    gen::out_conv::main();
+    gen::out_fallible::main();
+    gen::derives::main();
+    gen::owned::main();
+    gen::hooks::main();
+    gen::arg_types::main();
+    gen::lifetimes::main();
+    gen::receivers::main();
+    gen::parts::main();
+    gen::groups::main();
+    gen::attrs::main();
+    gen::async_fns::main();
+    gen::patterns::main();
+    gen::bind::main();
+    #[cfg(feature = "serde")]
+    gen::serde::main();
+    #[cfg(feature = "tracing")]
+    gen::traced::main();
 
     // endregion: gen
 
@@ -40,6 +66,8 @@ fn test_main() {
 
     impl_match::polymorphism::main();
     impl_match::polymorphism_lftm::main();
+    #[cfg(feature = "tracing")]
+    impl_match::traced::main();
 
     // endregion: impl_match
 }
@@ -0,0 +1,7 @@
+// the messages and spans of the macro errors: `TRYBUILD=overwrite cargo test --test ui`
+// updates the `.stderr` files
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
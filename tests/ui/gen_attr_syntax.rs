@@ -0,0 +1,27 @@
+struct Post {
+    content: String,
+}
+
+#[methods_enum::gen(Meth run)]
+impl Post {
+    pub fn content(&mut self) -> &str;
+
+    fn run(&mut self, method: Meth) -> &str {
+        &self.content
+    }
+}
+
+struct Doc {
+    text: String,
+}
+
+#[methods_enum::gen(Op, run; nameed)]
+impl Doc {
+    pub fn text(&mut self) -> &str;
+
+    fn run(&mut self, op: Op) -> &str {
+        &self.text
+    }
+}
+
+fn main() {}
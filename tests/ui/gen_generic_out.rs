@@ -0,0 +1,18 @@
+use std::fmt::Display;
+
+struct Log {
+    lines: Vec<String>,
+}
+
+#[methods_enum::gen(Meth, run)]
+impl Log {
+    pub fn clear(&mut self);
+    pub fn push<T: Display>(&mut self, x: T) -> T;
+
+    fn run(&mut self, meth: Meth) {
+        let Meth::clear() = meth;
+        self.lines.clear();
+    }
+}
+
+fn main() {}
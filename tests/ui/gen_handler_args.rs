@@ -0,0 +1,14 @@
+struct Post {
+    content: String,
+}
+
+#[methods_enum::gen(Meth, run)]
+impl Post {
+    pub fn content(&mut self) -> &str;
+
+    fn run(&mut self, _method: String) -> &str {
+        &self.content
+    }
+}
+
+fn main() {}
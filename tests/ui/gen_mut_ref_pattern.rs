@@ -0,0 +1,16 @@
+struct Counter {
+    n: i32,
+}
+
+#[methods_enum::gen(Meth, run)]
+impl Counter {
+    pub fn add_to(&mut self, &mut z: &mut i32);
+    pub fn add(&mut self, (x, mut y): (i32, i32));
+
+    fn run(&mut self, meth: Meth) {
+        let Meth::add((x, y)) = meth;
+        self.n += x + y;
+    }
+}
+
+fn main() {}
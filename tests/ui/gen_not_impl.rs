@@ -0,0 +1,6 @@
+#[methods_enum::gen(Meth, run)]
+struct Post {
+    content: String,
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+struct Counter {
+    n: i32,
+}
+
+#[methods_enum::gen(Meth, run, Out)]
+impl Counter {
+    pub fn get(&mut self) -> Nope;
+    pub fn add(&mut self, x: i32) -> i32;
+
+    fn run(&mut self, meth: Meth) -> Out {
+        match meth {
+            Meth::get() => Out::Unit,
+            Meth::add(x) => {
+                self.n += x;
+                Out::add(self.n)
+            }
+        }
+    }
+}
+
+fn main() {}
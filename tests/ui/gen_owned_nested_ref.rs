@@ -0,0 +1,19 @@
+#[derive(Default)]
+struct Editor {
+    text: String,
+}
+
+#[methods_enum::gen(Cmd, run; owned)]
+impl Editor {
+    pub fn insert(&mut self, text: &str);
+    pub fn insert_opt(&mut self, text: Option<&str>);
+
+    fn run(&mut self, cmd: Cmd) {
+        match cmd {
+            Cmd::insert(text) | Cmd::insert_opt(Some(text)) => self.text.push_str(text),
+            Cmd::insert_opt(None) => {}
+        }
+    }
+}
+
+fn main() {}
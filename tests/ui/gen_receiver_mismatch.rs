@@ -0,0 +1,18 @@
+struct Post {
+    content: String,
+}
+
+#[methods_enum::gen(Meth, run)]
+impl Post {
+    pub fn content(&mut self) -> &str;
+    pub fn len(&self) -> usize;
+    pub fn take(self) -> String;
+
+    fn run(&mut self, method: Meth) -> &str {
+        match method {
+            _ => &self.content,
+        }
+    }
+}
+
+fn main() {}
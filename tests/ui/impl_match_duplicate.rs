@@ -0,0 +1,15 @@
+methods_enum::impl_match! {
+
+impl Light {
+    fn switch(&mut self) ~{ match self {} }
+}
+
+enum Light {
+    On:     switch() { *self = Light::Off }
+            switch() { *self = Light::On },
+    Off:    switch() { *self = Light::On }
+}
+
+}
+
+fn main() {}
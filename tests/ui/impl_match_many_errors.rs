@@ -0,0 +1,16 @@
+// all the errors of the macro are reported at once
+methods_enum::impl_match! {
+
+impl Light {
+    fn switch(&mut self) ~{ match self {} }
+}
+
+enum Light {
+    On:     switch() { *self = Light::Off }
+            switch() { *self = Light::On },
+    Off:    switch() => { *self = Light::On }
+}
+
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+methods_enum::impl_match! {
+
+impl Light {
+    fn switch(&mut self) ~{ match self {} }
+}
+
+enum Light {
+    On:     switch() { *self = Light::Off },
+    Off:    swich() { *self = Light::On }
+}
+
+}
+
+fn main() {}